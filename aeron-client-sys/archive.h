@@ -0,0 +1 @@
+#include "client/aeron_archive.h"
@@ -10,35 +10,66 @@ pub fn main() -> Result<(), Box<dyn Error + 'static>> {
 
     let src_dir = Path::new("aeron").canonicalize()?;
 
-    let build_dir = Config::new(&src_dir)
+    let embedded_driver = env::var_os("CARGO_FEATURE_EMBEDDED_DRIVER").is_some();
+    let archive = env::var_os("CARGO_FEATURE_ARCHIVE").is_some();
+
+    let mut config = Config::new(&src_dir);
+    config
         .define("C_WARNINGS_AS_ERRORS", "TRUE")
         .define("STANDALONE_BUILD", "FALSE")
-        .define("BUILD_AERON_DRIVER", "OFF")
-        .define("BUILD_AERON_ARCHIVE_API", "OFF")
+        .define("BUILD_AERON_DRIVER", if embedded_driver { "ON" } else { "OFF" })
+        .define("BUILD_AERON_ARCHIVE_API", if archive { "ON" } else { "OFF" })
         .define("AERON_TESTS", "FALSE")
         .define("AERON_UNIT_TESTS", "FALSE")
         .define("AERON_SYSTEM_TESTS", "FALSE")
         .define("AERON_BUILD_SAMPLES", "FALSE")
         .define("AERON_BUILD_DOCUMENTATION", "FALSE")
-        .define("AERON_INSTALL_TARGETS", "FALSE")
-        .build_target("aeron_static")
-        .build();
+        .define("AERON_INSTALL_TARGETS", "FALSE");
+
+    let build_dir = config.build_target("aeron_static").build();
+    if embedded_driver {
+        config.build_target("aeron_driver_static").build();
+    }
+    if archive {
+        config.build_target("aeron_archive_c_client_static").build();
+    }
 
     let includes = src_dir.join("aeron-client/src/main/c");
     println!("cargo:include={}", includes.display());
 
     let libs = build_dir.join("build/lib");
+    if embedded_driver {
+        println!("cargo:rustc-link-lib=static=aeron_driver_static");
+    }
+    if archive {
+        println!("cargo:rustc-link-lib=static=aeron_archive_c_client_static");
+    }
     println!("cargo:rustc-link-lib=static=aeron_static");
     println!("cargo:rustc-link-search=native={}", libs.display());
+    if embedded_driver && env::var("CARGO_CFG_TARGET_OS")? == "linux" {
+        for lib in ["dl", "m", "pthread"] {
+            println!("cargo:rustc-link-lib={lib}");
+        }
+    }
 
-    bindgen::builder()
+    let mut bindings = bindgen::builder()
         .use_core()
         .header("wrapper.h")
         .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
         .allowlist_function("aeron_.*")
-        .clang_arg(format!("-I{}", includes.display()))
-        .generate()?
-        .write_to_file(out_dir.join("bindings.rs"))?;
+        .allowlist_var("AERON_.*_ENV_VAR")
+        .clang_arg(format!("-I{}", includes.display()));
+    if embedded_driver {
+        let driver_includes = src_dir.join("aeron-driver/src/main/c");
+        bindings =
+            bindings.header("driver.h").clang_arg(format!("-I{}", driver_includes.display()));
+    }
+    if archive {
+        let archive_includes = src_dir.join("aeron-archive/src/main/c");
+        bindings =
+            bindings.header("archive.h").clang_arg(format!("-I{}", archive_includes.display()));
+    }
+    bindings.generate()?.write_to_file(out_dir.join("bindings.rs"))?;
 
     Ok(())
 }
@@ -0,0 +1 @@
+#include "aeronmd.h"
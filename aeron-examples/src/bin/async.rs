@@ -1,4 +1,4 @@
-use aeron::{client::Aeron, context::Context, publication::OfferResult, Header, StreamId};
+use aeron::{client::Aeron, context::Context, publication::OfferResult, HeaderRef, StreamId};
 use std::{
     sync::atomic::{AtomicBool, Ordering},
     time::Duration,
@@ -53,7 +53,7 @@ pub async fn main() -> color_eyre::Result<()> {
 
     let handle = tokio::spawn(async move {
         let stop = AtomicBool::new(false);
-        let handler = |data: &[u8], header: Header| {
+        let handler = |data: &[u8], header: HeaderRef| {
             let text = String::from_utf8_lossy(data);
             println!(
                 "S: Message from session {sess_id:?} ({len} bytes) <<{text}>>",
@@ -66,8 +66,9 @@ pub async fn main() -> color_eyre::Result<()> {
         };
 
         while !stop.load(Ordering::Acquire) {
-            subscription.poll(handler, 1);
-            tokio::time::sleep(Duration::from_millis(100)).await;
+            if subscription.poll(handler, 1).unwrap() == 0 {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
         }
     });
 
@@ -86,17 +87,27 @@ pub async fn main() -> color_eyre::Result<()> {
             OfferResult::AdminAction => {
                 println!("P: admin action. retying.");
             }
+            OfferResult::Closed | OfferResult::MaxPositionExceeded => {
+                println!("P: publication can't be used anymore.");
+                break;
+            }
         };
         tokio::time::sleep(Duration::from_millis(500)).await;
     }
 
-    let (mut buf, _) = publication.try_claim(26)?;
+    let (mut buf, _) = loop {
+        if let Some(claim) = publication.try_claim(26)?.into_result()? {
+            break claim;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    };
     for i in 0..buf.data().len() {
         buf.data()[i] = b'a' + i as u8;
     }
     buf.commit()?;
 
-    publication.offer(&Vec::from("stop".as_bytes()))?;
+    let position = publication.send(b"stop").await?;
+    println!("P: SENT stop {position:?}");
 
     handle.await?;
 
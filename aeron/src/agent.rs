@@ -0,0 +1,402 @@
+//! Agents structure a service as duty cycles, e.g. polling subscriptions and
+//! offering to publications, that run on a thread of their own or are
+//! invoked from an existing one.
+//!
+//! These mirror the `Agent` framework of the Java and C clients.
+
+use crate::{
+    error::{Error, Result},
+    idle::IdleStrategy,
+};
+use std::{
+    ffi::CString,
+    panic,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+/// A unit of work that is called repeatedly by an [`AgentRunner`] or an
+/// [`AgentInvoker`].
+pub trait Agent {
+    /// Called once on the agent's thread before the first duty cycle.
+    fn on_start(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Does one duty cycle and returns the amount of work done, e.g. the
+    /// fragments polled. Zero lets the idle strategy idle.
+    fn do_work(&mut self) -> Result<usize>;
+
+    /// Called once on the agent's thread after the last duty cycle.
+    fn on_close(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// The name of the agent, also used for the thread of an [`AgentRunner`].
+    fn role_name(&self) -> &str;
+}
+
+impl<A: Agent + ?Sized> Agent for Box<A> {
+    fn on_start(&mut self) -> Result<()> {
+        (**self).on_start()
+    }
+
+    fn do_work(&mut self) -> Result<usize> {
+        (**self).do_work()
+    }
+
+    fn on_close(&mut self) -> Result<()> {
+        (**self).on_close()
+    }
+
+    fn role_name(&self) -> &str {
+        (**self).role_name()
+    }
+}
+
+type ErrorHandler = Box<dyn FnMut(Error) + Send>;
+
+/// Runs an agent on the calling thread, one duty cycle per
+/// [`AgentInvoker::invoke`].
+///
+/// Errors of the agent are passed to the error handler. An agent that fails to
+/// start is closed right away. The agent is closed on drop if it was started.
+pub struct AgentInvoker<A: Agent> {
+    agent: A,
+    error_handler: ErrorHandler,
+    started: bool,
+    running: bool,
+    closed: bool,
+}
+
+impl<A: Agent> AgentInvoker<A> {
+    pub fn new<E>(agent: A, error_handler: E) -> Self
+    where
+        E: FnMut(Error) + Send + 'static,
+    {
+        AgentInvoker {
+            agent,
+            error_handler: Box::new(error_handler),
+            started: false,
+            running: false,
+            closed: false,
+        }
+    }
+
+    pub fn agent(&self) -> &A {
+        &self.agent
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.started
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Calls [`Agent::on_start`] unless the agent was started before.
+    pub fn start(&mut self) {
+        if self.started {
+            return;
+        }
+        self.started = true;
+        match self.agent.on_start() {
+            Ok(()) => self.running = true,
+            Err(e) => {
+                (self.error_handler)(e);
+                self.close();
+            }
+        }
+    }
+
+    /// Does one duty cycle if the agent is running and returns the amount of
+    /// work done.
+    pub fn invoke(&mut self) -> usize {
+        if !self.running {
+            return 0;
+        }
+        match self.agent.do_work() {
+            Ok(work_count) => work_count,
+            Err(e) => {
+                (self.error_handler)(e);
+                0
+            }
+        }
+    }
+
+    /// Calls [`Agent::on_close`] unless the agent was closed before.
+    pub fn close(&mut self) {
+        if self.closed {
+            return;
+        }
+        self.running = false;
+        self.closed = true;
+        if let Err(e) = self.agent.on_close() {
+            (self.error_handler)(e);
+        }
+    }
+}
+
+impl<A: Agent> Drop for AgentInvoker<A> {
+    fn drop(&mut self) {
+        if self.started {
+            self.close();
+        }
+    }
+}
+
+/// Runs an agent on a dedicated thread, idling with an [`IdleStrategy`]
+/// between duty cycles.
+///
+/// The thread is named after [`Agent::role_name`]. Dropping the runner stops
+/// the agent and waits for the thread like [`AgentRunner::close`].
+pub struct AgentRunner {
+    role_name: String,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl AgentRunner {
+    /// Starts the agent on a new thread. Errors of the agent are passed to
+    /// `error_handler` on that thread.
+    ///
+    /// Fails if the role name contains a NUL byte, as it can't name the thread.
+    pub fn start<A, I, E>(agent: A, mut idle_strategy: I, error_handler: E) -> Result<Self>
+    where
+        A: Agent + Send + 'static,
+        I: IdleStrategy + Send + 'static,
+        E: FnMut(Error) + Send + 'static,
+    {
+        let role_name = agent.role_name().to_owned();
+        if role_name.as_bytes().contains(&0) {
+            return Err(CString::new(role_name).unwrap_err().into());
+        }
+        let running = Arc::new(AtomicBool::new(true));
+        let thread = thread::Builder::new().name(role_name.clone()).spawn({
+            let running = running.clone();
+            move || {
+                let mut invoker = AgentInvoker::new(agent, error_handler);
+                invoker.start();
+                while invoker.is_running() && running.load(Ordering::Acquire) {
+                    idle_strategy.idle(invoker.invoke());
+                }
+                invoker.close();
+                running.store(false, Ordering::Release);
+            }
+        })?;
+        Ok(AgentRunner { role_name, running, thread: Some(thread) })
+    }
+
+    pub fn role_name(&self) -> &str {
+        &self.role_name
+    }
+
+    /// Whether the agent is still doing duty cycles. Turns false once the
+    /// runner is closed or the agent failed to start.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// Stops the agent after its current duty cycle and waits until it is
+    /// closed. Resumes the panic if the agent panicked.
+    pub fn close(mut self) {
+        if let Err(panic) = self.stop() {
+            panic::resume_unwind(panic);
+        }
+    }
+
+    fn stop(&mut self) -> thread::Result<()> {
+        self.running.store(false, Ordering::Release);
+        match self.thread.take() {
+            Some(thread) => thread.join(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for AgentRunner {
+    fn drop(&mut self) {
+        self.stop().ok();
+    }
+}
+
+/// Combines several agents into one, so they share a thread. Each duty cycle
+/// runs every agent once.
+///
+/// If an agent fails a duty cycle, the other agents still run and the first
+/// error is returned, so it reaches the error handler of the runner or invoker
+/// and one failing agent doesn't starve the others.
+pub struct CompositeAgent {
+    agents: Vec<Box<dyn Agent + Send>>,
+    role_name: String,
+}
+
+impl CompositeAgent {
+    pub fn new(agents: Vec<Box<dyn Agent + Send>>) -> Self {
+        let names: Vec<&str> = agents.iter().map(|agent| agent.role_name()).collect();
+        let role_name = format!("[{}]", names.join(","));
+        CompositeAgent { agents, role_name }
+    }
+}
+
+impl Agent for CompositeAgent {
+    /// Starts every agent and returns the first error.
+    fn on_start(&mut self) -> Result<()> {
+        let mut result = Ok(());
+        for agent in &mut self.agents {
+            let res = agent.on_start();
+            result = result.and(res);
+        }
+        result
+    }
+
+    /// Runs every agent and returns the first error.
+    fn do_work(&mut self) -> Result<usize> {
+        let mut result = Ok(0);
+        for agent in &mut self.agents {
+            let res = agent.do_work();
+            result = result.and_then(|work_count| Ok(work_count + res?));
+        }
+        result
+    }
+
+    /// Closes every agent and returns the first error.
+    fn on_close(&mut self) -> Result<()> {
+        let mut result = Ok(());
+        for agent in &mut self.agents {
+            let res = agent.on_close();
+            result = result.and(res);
+        }
+        result
+    }
+
+    fn role_name(&self) -> &str {
+        &self.role_name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::idle::NoOp;
+    use std::sync::{mpsc, Mutex};
+
+    /// Records its duty cycles in a log shared with other agents.
+    struct Recorder {
+        name: &'static str,
+        log: Arc<Mutex<Vec<&'static str>>>,
+        work_count: usize,
+        error: Option<fn() -> Error>,
+    }
+
+    impl Recorder {
+        fn new(name: &'static str, log: &Arc<Mutex<Vec<&'static str>>>) -> Box<Self> {
+            Box::new(Recorder { name, log: log.clone(), work_count: 1, error: None })
+        }
+    }
+
+    impl Agent for Recorder {
+        fn do_work(&mut self) -> Result<usize> {
+            self.log.lock().unwrap().push(self.name);
+            match self.error {
+                Some(error) => Err(error()),
+                None => Ok(self.work_count),
+            }
+        }
+
+        fn role_name(&self) -> &str {
+            self.name
+        }
+    }
+
+    #[test]
+    fn composite_runs_agents_in_order() {
+        let log = Arc::default();
+        let mut agent = CompositeAgent::new(vec![
+            Recorder::new("a", &log),
+            Recorder::new("b", &log),
+            Recorder::new("c", &log),
+        ]);
+        assert_eq!(agent.role_name(), "[a,b,c]");
+
+        assert_eq!(agent.do_work().unwrap(), 3);
+        assert_eq!(agent.do_work().unwrap(), 3);
+        assert_eq!(*log.lock().unwrap(), ["a", "b", "c", "a", "b", "c"]);
+    }
+
+    #[test]
+    fn composite_returns_first_error_after_running_every_agent() {
+        let log = Arc::default();
+        let mut agents = vec![
+            Recorder::new("a", &log),
+            Recorder::new("b", &log),
+            Recorder::new("c", &log),
+            Recorder::new("d", &log),
+        ];
+        agents[1].error = Some(|| Error::NotConnected);
+        agents[2].error = Some(|| Error::TimedOut);
+        let mut agent = CompositeAgent::new(agents.into_iter().map(|a| a as _).collect());
+
+        assert!(matches!(agent.do_work(), Err(Error::NotConnected)));
+        assert_eq!(*log.lock().unwrap(), ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn invoker_reports_composite_errors() {
+        let log = Arc::default();
+        let mut failing = Recorder::new("b", &log);
+        failing.error = Some(|| Error::NotConnected);
+        let agent = CompositeAgent::new(vec![Recorder::new("a", &log), failing]);
+        let (tx, rx) = mpsc::channel();
+        let mut invoker = AgentInvoker::new(agent, move |e| tx.send(e).unwrap());
+
+        invoker.start();
+        assert_eq!(invoker.invoke(), 0);
+        assert!(matches!(rx.try_recv(), Ok(Error::NotConnected)));
+        assert!(invoker.is_running());
+    }
+
+    struct FailsToStart;
+
+    impl Agent for FailsToStart {
+        fn on_start(&mut self) -> Result<()> {
+            Err(Error::NotConnected)
+        }
+
+        fn do_work(&mut self) -> Result<usize> {
+            panic!("agent was not started");
+        }
+
+        fn role_name(&self) -> &str {
+            "fails-to-start"
+        }
+    }
+
+    #[test]
+    fn invoker_closes_agent_that_fails_to_start() {
+        let (tx, rx) = mpsc::channel();
+        let mut invoker = AgentInvoker::new(FailsToStart, move |e| tx.send(e).unwrap());
+
+        invoker.start();
+        assert!(invoker.is_started());
+        assert!(!invoker.is_running());
+        assert!(invoker.is_closed());
+        assert!(matches!(rx.try_recv(), Ok(Error::NotConnected)));
+        assert_eq!(invoker.invoke(), 0);
+    }
+
+    #[test]
+    fn runner_rejects_role_name_with_nul() {
+        let agent = Recorder::new("nul\0", &Arc::default());
+        let result = AgentRunner::start(agent, NoOp, |e| panic!("{e}"));
+        assert!(matches!(result, Err(Error::NulError(_))));
+    }
+}
@@ -0,0 +1,535 @@
+//! Client for Aeron Archive.
+//!
+//! Apart from connecting, the C archive client only offers blocking
+//! request/response calls. Requests therefore run one after the other on a
+//! worker thread of the [`AeronArchive`] and are exposed as [`ArchiveRequest`]
+//! futures, so awaiting them never blocks the executor.
+
+use crate::{
+    client::Aeron,
+    error::{aeron_error, aeron_result, Error, Result},
+    CounterId, Position, RegistrationId, SendSyncPtr, SessionId, StreamId,
+};
+use aeron_client_sys as sys;
+use std::{
+    ffi::{c_void, CString},
+    future::Future,
+    io, mem,
+    pin::Pin,
+    ptr, slice,
+    sync::{mpsc, Arc, Mutex, PoisonError},
+    task::{self, Poll, Waker},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+#[derive(Copy, Clone, Debug)]
+pub struct RecordingId(pub i64);
+
+pub struct ArchiveContext {
+    inner: SendSyncPtr<sys::aeron_archive_context_t>,
+    closed: bool,
+}
+
+impl ArchiveContext {
+    pub fn new() -> Result<Self> {
+        let mut inner = ptr::null_mut();
+        aeron_result(unsafe { sys::aeron_archive_context_init(&mut inner) })?;
+        Ok(ArchiveContext { inner: inner.into(), closed: false })
+    }
+
+    pub fn set_control_request_channel(&mut self, channel: &str) -> Result<()> {
+        let channel = CString::new(channel)?;
+        aeron_result(unsafe {
+            sys::aeron_archive_context_set_control_request_channel(
+                self.inner.as_ptr(),
+                channel.as_ptr(),
+            )
+        })
+    }
+
+    pub fn set_control_request_stream_id(&mut self, stream_id: StreamId) -> Result<()> {
+        aeron_result(unsafe {
+            sys::aeron_archive_context_set_control_request_stream_id(
+                self.inner.as_ptr(),
+                stream_id.0,
+            )
+        })
+    }
+
+    pub fn set_control_response_channel(&mut self, channel: &str) -> Result<()> {
+        let channel = CString::new(channel)?;
+        aeron_result(unsafe {
+            sys::aeron_archive_context_set_control_response_channel(
+                self.inner.as_ptr(),
+                channel.as_ptr(),
+            )
+        })
+    }
+
+    pub fn set_control_response_stream_id(&mut self, stream_id: StreamId) -> Result<()> {
+        aeron_result(unsafe {
+            sys::aeron_archive_context_set_control_response_stream_id(
+                self.inner.as_ptr(),
+                stream_id.0,
+            )
+        })
+    }
+
+    pub fn set_message_timeout(&mut self, timeout: Duration) -> Result<()> {
+        let ns = u64::try_from(timeout.as_nanos()).unwrap_or(u64::MAX);
+        aeron_result(unsafe {
+            sys::aeron_archive_context_set_message_timeout_ns(self.inner.as_ptr(), ns)
+        })
+    }
+
+    /// Closes the context and reports whether it was released. Dropping the
+    /// context closes it as well, but ignores errors.
+    pub fn close(mut self) -> Result<()> {
+        self.close_in_place()
+    }
+
+    fn close_in_place(&mut self) -> Result<()> {
+        if mem::replace(&mut self.closed, true) {
+            return Ok(());
+        }
+        aeron_result(unsafe { sys::aeron_archive_context_close(self.inner.as_ptr()) })
+    }
+}
+
+impl Drop for ArchiveContext {
+    fn drop(&mut self) {
+        self.close_in_place().ok();
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum SourceLocation {
+    Local = sys::aeron_archive_source_location_en_AERON_ARCHIVE_SOURCE_LOCATION_LOCAL,
+    Remote = sys::aeron_archive_source_location_en_AERON_ARCHIVE_SOURCE_LOCATION_REMOTE,
+}
+
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct RecordingDescriptor {
+    pub control_session_id: i64,
+    pub correlation_id: i64,
+    pub recording_id: RecordingId,
+    pub start_timestamp: i64,
+    pub stop_timestamp: i64,
+    pub start_position: Position,
+    pub stop_position: Position,
+    pub initial_term_id: i32,
+    pub segment_file_length: i32,
+    pub term_buffer_length: i32,
+    pub mtu_length: i32,
+    pub session_id: SessionId,
+    pub stream_id: StreamId,
+    pub stripped_channel: String,
+    pub original_channel: String,
+    pub source_identity: String,
+}
+
+/// Which part of a recording to replay. Unset fields replay from the start of
+/// the recording and follow it if it is still live.
+#[derive(Clone, Debug, Default)]
+pub struct ReplayParams {
+    pub position: Option<Position>,
+    pub length: Option<i64>,
+    pub bounding_limit_counter_id: Option<CounterId>,
+    pub file_io_max_length: Option<i32>,
+}
+
+struct ArchiveInner {
+    inner: SendSyncPtr<sys::aeron_archive_t>,
+    // Closed after the archive client that uses them.
+    context: ArchiveContext,
+    _client: Arc<Aeron>,
+    closed: bool,
+}
+
+impl ArchiveInner {
+    fn close_in_place(&mut self) -> Result<()> {
+        if mem::replace(&mut self.closed, true) {
+            return Ok(());
+        }
+        aeron_result(unsafe { sys::aeron_archive_close(self.inner.as_ptr()) })
+    }
+}
+
+impl Drop for ArchiveInner {
+    fn drop(&mut self) {
+        self.close_in_place().ok();
+    }
+}
+
+type Request = Box<dyn FnOnce(*mut sys::aeron_archive_t) + Send>;
+
+pub struct AeronArchive {
+    inner: Arc<ArchiveInner>,
+    // The C archive client isn't thread safe, so a single worker runs the
+    // requests in the order they were made.
+    requests: Option<mpsc::Sender<Request>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl AeronArchive {
+    pub fn connect(client: &Arc<Aeron>, context: ArchiveContext) -> Result<ConnectArchive> {
+        Ok(ConnectArchive {
+            client: client.clone(),
+            state: Some(ConnectArchiveState::Unstarted { context }),
+        })
+    }
+
+    fn start(inner: ArchiveInner) -> Result<Self> {
+        let inner = Arc::new(inner);
+        let (requests, receiver) = mpsc::channel::<Request>();
+        let archive = inner.clone();
+        let worker = thread::Builder::new().name("aeron-archive".to_owned()).spawn(move || {
+            for request in receiver {
+                request(archive.inner.as_ptr());
+            }
+        })?;
+        Ok(AeronArchive { inner, requests: Some(requests), worker: Some(worker) })
+    }
+
+    /// Waits for pending requests, then closes the archive client and its
+    /// context, and reports whether both were released. Dropping the archive
+    /// closes it as well, but ignores errors.
+    ///
+    /// Fails with [`Error::ClientInUse`] if the archive client is still
+    /// referenced elsewhere.
+    pub fn close(mut self) -> Result<()> {
+        self.stop_worker();
+        let inner = Arc::get_mut(&mut self.inner).ok_or(Error::ClientInUse)?;
+        inner.close_in_place()?;
+        inner.context.close_in_place()
+    }
+
+    fn stop_worker(&mut self) {
+        // The worker finishes the queued requests and exits once the sender
+        // is gone, releasing its reference to the archive client.
+        self.requests = None;
+        if let Some(worker) = self.worker.take() {
+            worker.join().ok();
+        }
+    }
+
+    /// Starts recording a channel and stream, returning the registration id of
+    /// the recording subscription.
+    pub fn start_recording(
+        &self,
+        channel: &str,
+        stream_id: StreamId,
+        source_location: SourceLocation,
+        auto_stop: bool,
+    ) -> Result<ArchiveRequest<RegistrationId>> {
+        let channel = CString::new(channel)?;
+        Ok(self.request(move |archive| {
+            let mut subscription_id = 0;
+            aeron_result(unsafe {
+                sys::aeron_archive_start_recording(
+                    &mut subscription_id,
+                    archive,
+                    channel.as_ptr(),
+                    stream_id.0,
+                    source_location as sys::aeron_archive_source_location_t,
+                    auto_stop,
+                )
+            })?;
+            Ok(RegistrationId(subscription_id))
+        }))
+    }
+
+    pub fn stop_recording(&self, subscription_id: RegistrationId) -> ArchiveRequest<()> {
+        self.request(move |archive| {
+            aeron_result(unsafe {
+                sys::aeron_archive_stop_recording_subscription(archive, subscription_id.0)
+            })
+        })
+    }
+
+    pub fn stop_recording_channel(
+        &self,
+        channel: &str,
+        stream_id: StreamId,
+    ) -> Result<ArchiveRequest<()>> {
+        let channel = CString::new(channel)?;
+        Ok(self.request(move |archive| {
+            aeron_result(unsafe {
+                sys::aeron_archive_stop_recording_channel_and_stream(
+                    archive,
+                    channel.as_ptr(),
+                    stream_id.0,
+                )
+            })
+        }))
+    }
+
+    /// Lists up to `record_count` recordings starting at `from_recording_id`.
+    pub fn list_recordings(
+        &self,
+        from_recording_id: RecordingId,
+        record_count: i32,
+    ) -> ArchiveRequest<Vec<RecordingDescriptor>> {
+        self.request(move |archive| {
+            let mut recordings: Vec<RecordingDescriptor> = Vec::new();
+            let mut count = 0;
+            aeron_result(unsafe {
+                sys::aeron_archive_list_recordings(
+                    &mut count,
+                    archive,
+                    from_recording_id.0,
+                    record_count,
+                    Some(recording_descriptor_trampoline),
+                    &mut recordings as *mut _ as *mut c_void,
+                )
+            })?;
+            Ok(recordings)
+        })
+    }
+
+    /// Starts a replay and returns the replay session id. The low 32 bits are
+    /// the session id of the replayed image.
+    pub fn start_replay(
+        &self,
+        recording_id: RecordingId,
+        replay_channel: &str,
+        replay_stream_id: StreamId,
+        params: &ReplayParams,
+    ) -> Result<ArchiveRequest<i64>> {
+        let replay_channel = CString::new(replay_channel)?;
+        let params = params.clone();
+        Ok(self.request(move |archive| {
+            let mut replay_params = unsafe {
+                let mut replay_params = std::mem::zeroed();
+                aeron_result(sys::aeron_archive_replay_params_init(&mut replay_params))?;
+                replay_params
+            };
+            if let Some(position) = params.position {
+                replay_params.position = position.0;
+            }
+            if let Some(length) = params.length {
+                replay_params.length = length;
+            }
+            if let Some(counter_id) = params.bounding_limit_counter_id {
+                replay_params.bounding_limit_counter_id = counter_id.0;
+            }
+            if let Some(length) = params.file_io_max_length {
+                replay_params.file_io_max_length = length;
+            }
+            let mut replay_session_id = 0;
+            aeron_result(unsafe {
+                sys::aeron_archive_start_replay(
+                    &mut replay_session_id,
+                    archive,
+                    recording_id.0,
+                    replay_channel.as_ptr(),
+                    replay_stream_id.0,
+                    &mut replay_params,
+                )
+            })?;
+            Ok(replay_session_id)
+        }))
+    }
+
+    pub fn stop_replay(&self, replay_session_id: i64) -> ArchiveRequest<()> {
+        self.request(move |archive| {
+            aeron_result(unsafe { sys::aeron_archive_stop_replay(archive, replay_session_id) })
+        })
+    }
+
+    /// Truncates a stopped recording to `position` and returns the number of
+    /// segment files deleted.
+    pub fn truncate_recording(
+        &self,
+        recording_id: RecordingId,
+        position: Position,
+    ) -> ArchiveRequest<i64> {
+        self.request(move |archive| {
+            let mut count = 0;
+            aeron_result(unsafe {
+                sys::aeron_archive_truncate_recording(
+                    &mut count,
+                    archive,
+                    recording_id.0,
+                    position.0,
+                )
+            })?;
+            Ok(count)
+        })
+    }
+
+    fn request<T, F>(&self, request: F) -> ArchiveRequest<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(*mut sys::aeron_archive_t) -> Result<T> + Send + 'static,
+    {
+        let state = Arc::new(Mutex::new(RequestState { result: None, waker: None }));
+        let completion = state.clone();
+        let job: Request = Box::new(move |archive| {
+            // Errors are read from thread-local state, so they must be
+            // converted on the worker.
+            complete(&completion, request(archive));
+        });
+        let sent = self.requests.as_ref().is_some_and(|requests| requests.send(job).is_ok());
+        if !sent {
+            let stopped = io::Error::new(io::ErrorKind::BrokenPipe, "archive worker stopped");
+            complete(&state, Err(stopped.into()));
+        }
+        ArchiveRequest { state }
+    }
+}
+
+impl Drop for AeronArchive {
+    fn drop(&mut self) {
+        self.stop_worker();
+    }
+}
+
+struct RequestState<T> {
+    result: Option<Result<T>>,
+    waker: Option<Waker>,
+}
+
+fn complete<T>(state: &Mutex<RequestState<T>>, result: Result<T>) {
+    let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+    state.result = Some(result);
+    if let Some(waker) = state.waker.take() {
+        waker.wake();
+    }
+}
+
+/// A request to the archive, queued on its worker thread.
+#[must_use = "future must be polled"]
+pub struct ArchiveRequest<T> {
+    state: Arc<Mutex<RequestState<T>>>,
+}
+
+impl<T> Future for ArchiveRequest<T> {
+    type Output = Result<T>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(ctx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+unsafe extern "C" fn recording_descriptor_trampoline(
+    descriptor: *mut sys::aeron_archive_recording_descriptor_t,
+    clientd: *mut c_void,
+) {
+    let recordings = &mut *(clientd as *mut Vec<RecordingDescriptor>);
+    let d = &*descriptor;
+    let string = |ptr: *mut i8, len: usize| {
+        if ptr.is_null() {
+            String::new()
+        } else {
+            String::from_utf8_lossy(slice::from_raw_parts(ptr as *const u8, len)).into_owned()
+        }
+    };
+    recordings.push(RecordingDescriptor {
+        control_session_id: d.control_session_id,
+        correlation_id: d.correlation_id,
+        recording_id: RecordingId(d.recording_id),
+        start_timestamp: d.start_timestamp,
+        stop_timestamp: d.stop_timestamp,
+        start_position: Position(d.start_position),
+        stop_position: Position(d.stop_position),
+        initial_term_id: d.initial_term_id,
+        segment_file_length: d.segment_file_length,
+        term_buffer_length: d.term_buffer_length,
+        mtu_length: d.mtu_length,
+        session_id: SessionId(d.session_id),
+        stream_id: StreamId(d.stream_id),
+        stripped_channel: string(d.stripped_channel, d.stripped_channel_length),
+        original_channel: string(d.original_channel, d.original_channel_length),
+        source_identity: string(d.source_identity, d.source_identity_length),
+    });
+}
+
+#[must_use = "future must be polled"]
+pub struct ConnectArchive {
+    client: Arc<Aeron>,
+    state: Option<ConnectArchiveState>,
+}
+
+enum ConnectArchiveState {
+    Unstarted { context: ArchiveContext },
+    Polling { context: ArchiveContext, inner: SendSyncPtr<sys::aeron_archive_async_connect_t> },
+}
+
+impl Future for ConnectArchive {
+    type Output = Result<AeronArchive>;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let mut self_mut = self.as_mut();
+        match self_mut.state.take().expect("polled after completion") {
+            ConnectArchiveState::Unstarted { context } => {
+                let mut inner = ptr::null_mut();
+                let res = aeron_result(unsafe {
+                    sys::aeron_archive_context_set_aeron(
+                        context.inner.as_ptr(),
+                        self_mut.client.inner.as_ptr(),
+                    )
+                })
+                .and_then(|()| {
+                    aeron_result(unsafe {
+                        sys::aeron_archive_async_connect(&mut inner, context.inner.as_ptr())
+                    })
+                });
+                if let Err(e) = res {
+                    self_mut.state = Some(ConnectArchiveState::Unstarted { context });
+                    return Poll::Ready(Err(e));
+                }
+                debug_assert_ne!(inner, ptr::null_mut());
+
+                self_mut.state =
+                    Some(ConnectArchiveState::Polling { context, inner: inner.into() });
+                ctx.waker().wake_by_ref();
+                Poll::Pending
+            }
+            ConnectArchiveState::Polling { context, inner } => {
+                if let Err(e) = self_mut.client.do_work() {
+                    self_mut.state = Some(ConnectArchiveState::Polling { context, inner });
+                    return Poll::Ready(Err(e));
+                }
+                let mut archive = ptr::null_mut();
+                // The C client frees the async connect once it completes or fails.
+                match unsafe { sys::aeron_archive_async_connect_poll(&mut archive, inner.as_ptr()) }
+                {
+                    0 => {
+                        self_mut.state = Some(ConnectArchiveState::Polling { context, inner });
+                        ctx.waker().wake_by_ref();
+                        Poll::Pending
+                    }
+                    1 => {
+                        debug_assert_ne!(archive, ptr::null_mut());
+                        Poll::Ready(AeronArchive::start(ArchiveInner {
+                            inner: archive.into(),
+                            context,
+                            _client: self_mut.client.clone(),
+                            closed: false,
+                        }))
+                    }
+                    _ => Poll::Ready(Err(aeron_error())),
+                }
+            }
+        }
+    }
+}
+
+impl Drop for ConnectArchive {
+    fn drop(&mut self) {
+        if let Some(ConnectArchiveState::Polling { inner, .. }) = self.state.take() {
+            aeron_result(unsafe { sys::aeron_archive_async_connect_delete(inner.as_ptr()) }).ok();
+        }
+    }
+}
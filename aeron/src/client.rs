@@ -1,16 +1,34 @@
 use crate::{
     context::Context,
-    error::{aeron_result, Result},
+    counters::CountersReader,
+    error::{aeron_error, aeron_result, Error, Result},
+    exclusive_publication::AddExclusivePublication,
     publication::AddPublication,
     subscription::AddSubscription,
-    SendSyncPtr, StreamId,
+    ClientData, SendSyncPtr, StreamId,
 };
 use aeron_client_sys as sys;
-use std::{ptr, sync::Arc};
+use std::{
+    ffi::c_void,
+    future::Future,
+    mem,
+    pin::Pin,
+    ptr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    task::{self, Poll, Waker},
+};
 
 pub struct Aeron {
+    // Dropped after `aeron_close` so the callbacks it owns outlive the conductor.
     pub context: Context,
     pub(crate) inner: SendSyncPtr<sys::aeron_t>,
+    use_conductor_agent_invoker: bool,
+    // Held while a duty cycle runs; the conductor must not be invoked concurrently.
+    invoking: AtomicBool,
+    closed: bool,
 }
 
 impl Aeron {
@@ -18,7 +36,67 @@ impl Aeron {
         let mut inner = ptr::null_mut();
         aeron_result(unsafe { sys::aeron_init(&mut inner, context.inner.as_ptr()) })?;
         aeron_result(unsafe { sys::aeron_start(inner) })?;
-        Ok(Arc::new(Aeron { context, inner: inner.into() }))
+        let use_conductor_agent_invoker = context.get_use_conductor_agent_invoker();
+        Ok(Arc::new(Aeron {
+            context,
+            inner: inner.into(),
+            use_conductor_agent_invoker,
+            invoking: AtomicBool::new(false),
+            closed: false,
+        }))
+    }
+
+    pub fn is_using_conductor_agent_invoker(&self) -> bool {
+        self.use_conductor_agent_invoker
+    }
+
+    /// Runs one duty cycle of the client conductor and returns the amount of
+    /// work done.
+    ///
+    /// Only has an effect if the context enabled the conductor agent invoker,
+    /// otherwise the conductor runs on its own thread and this returns `Ok(0)`.
+    /// It also returns `Ok(0)` if another thread is already running a duty
+    /// cycle. The `AddPublication` and `AddSubscription` futures call this
+    /// while polling, so they complete without a separate invoker loop.
+    pub fn do_work(&self) -> Result<usize> {
+        if !self.use_conductor_agent_invoker || self.invoking.swap(true, Ordering::Acquire) {
+            return Ok(0);
+        }
+        let work_count = unsafe { sys::aeron_main_do_work(self.inner.as_ptr()) };
+        self.invoking.store(false, Ordering::Release);
+        if work_count < 0 {
+            return Err(aeron_error());
+        }
+        Ok(work_count as usize)
+    }
+
+    /// Runs one duty cycle like [`Aeron::do_work`] and then idles with the
+    /// conductor's idle strategy, which sleeps if there was no work to do.
+    pub fn invoke(&self) -> Result<usize> {
+        let work_count = self.do_work()?;
+        if self.use_conductor_agent_invoker {
+            unsafe { sys::aeron_main_idle_strategy(self.inner.as_ptr(), work_count as i32) };
+        }
+        Ok(work_count)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        unsafe { sys::aeron_is_closed(self.inner.as_ptr()) }
+    }
+
+    /// Resolves once the client has closed, with the reason it closed.
+    ///
+    /// Errors that terminate the client are only seen if the context has an
+    /// error handler, the default handler of the C client exits the process.
+    ///
+    /// The future does not keep the client alive, so it can be awaited by a
+    /// supervisor independently of the tasks using the client.
+    pub fn closed(&self) -> Closed {
+        Closed { state: self.context.close_state.clone() }
+    }
+
+    pub fn counters_reader(&self) -> CountersReader<'_> {
+        CountersReader::new(unsafe { sys::aeron_counters_reader(self.inner.as_ptr()) })
     }
 
     pub fn add_publication(
@@ -29,6 +107,14 @@ impl Aeron {
         AddPublication::new(self, uri, stream_id)
     }
 
+    pub fn add_exclusive_publication(
+        self: &Arc<Self>,
+        uri: &str,
+        stream_id: StreamId,
+    ) -> Result<AddExclusivePublication> {
+        AddExclusivePublication::new(self, uri, stream_id)
+    }
+
     pub fn add_subscription(
         self: &Arc<Self>,
         uri: &str,
@@ -36,10 +122,172 @@ impl Aeron {
     ) -> Result<AddSubscription> {
         AddSubscription::new(self, uri, stream_id)
     }
+
+    /// Closes the client and then its context, and reports whether both were
+    /// released. Dropping the last reference closes the client as well, but
+    /// ignores errors.
+    ///
+    /// Fails with [`Error::ClientInUse`] if publications, subscriptions or
+    /// other handles still hold a reference, so close those first.
+    pub fn close(self: Arc<Self>) -> Result<()> {
+        let mut client = Arc::try_unwrap(self).map_err(|_| Error::ClientInUse)?;
+        client.close_in_place()?;
+        client.context.close_in_place()
+    }
+
+    fn close_in_place(&mut self) -> Result<()> {
+        if mem::replace(&mut self.closed, true) {
+            return Ok(());
+        }
+        aeron_result(unsafe { sys::aeron_close(self.inner.as_ptr()) })
+    }
 }
 
 impl Drop for Aeron {
     fn drop(&mut self) {
-        aeron_result(unsafe { sys::aeron_close(self.inner.as_ptr()) }).ok(); // TODO: err
+        self.close_in_place().ok();
     }
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CloseReason {
+    /// The application closed the client.
+    UserClose,
+    /// The media driver stopped responding within the driver timeout.
+    DriverTimeout(String),
+    /// The conductor terminated itself, e.g. because the driver timed out the
+    /// client or the conductor service interval was exceeded.
+    ConductorError { code: i32, message: String },
+}
+
+#[derive(Default)]
+pub(crate) struct CloseState {
+    inner: Mutex<CloseStateInner>,
+}
+
+#[derive(Default)]
+struct CloseStateInner {
+    // The error that terminated the conductor, if any.
+    fatal_error: Option<CloseReason>,
+    reason: Option<CloseReason>,
+    wakers: Vec<Waker>,
+}
+
+impl CloseState {
+    /// Records the error that terminated the conductor. The client counts as
+    /// closed right away, since it can't be used anymore.
+    pub(crate) fn fatal_error(&self, reason: CloseReason) {
+        let mut inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        if inner.fatal_error.is_none() {
+            inner.fatal_error = Some(reason.clone());
+        }
+        inner.resolve(reason);
+    }
+
+    /// Records that the C client closed, because of the recorded fatal error
+    /// or else because the application closed it.
+    pub(crate) fn client_closed(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        let reason = inner.fatal_error.clone().unwrap_or(CloseReason::UserClose);
+        inner.resolve(reason);
+    }
+
+    fn poll(&self, ctx: &mut task::Context<'_>) -> Poll<CloseReason> {
+        let mut inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        match &inner.reason {
+            Some(reason) => Poll::Ready(reason.clone()),
+            None => {
+                if !inner.wakers.iter().any(|w| w.will_wake(ctx.waker())) {
+                    inner.wakers.push(ctx.waker().clone());
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl CloseStateInner {
+    /// The first reason wins.
+    fn resolve(&mut self, reason: CloseReason) {
+        if self.reason.is_none() {
+            self.reason = Some(reason);
+            self.wakers.drain(..).for_each(Waker::wake);
+        }
+    }
+}
+
+#[must_use = "future must be polled"]
+pub struct Closed {
+    state: Arc<CloseState>,
+}
+
+impl Future for Closed {
+    type Output = CloseReason;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        self.state.poll(ctx)
+    }
+}
+
+/// Resolves once the client conductor has released a closed publication or
+/// subscription.
+#[must_use = "future must be polled"]
+pub struct Closing {
+    client: Arc<Aeron>,
+    state: Arc<CloseComplete>,
+}
+
+struct CloseComplete {
+    complete: AtomicBool,
+    // Callbacks of the closed resource, which the conductor may still call
+    // until the close has completed.
+    retained: Mutex<Vec<ClientData>>,
+}
+
+impl Closing {
+    /// Calls `close` with an `on_close_complete` notification that completes
+    /// the returned future.
+    pub(crate) fn new<F>(client: &Arc<Aeron>, close: F) -> Result<Self>
+    where
+        F: FnOnce(sys::aeron_notification_t, *mut c_void) -> i32,
+    {
+        let state = Arc::new(CloseComplete {
+            complete: AtomicBool::new(false),
+            retained: Mutex::new(Vec::new()),
+        });
+        let clientd = Arc::into_raw(state.clone()) as *mut c_void;
+        if let Err(e) = aeron_result(close(Some(close_complete_trampoline), clientd)) {
+            // The notification is never called if the close was rejected.
+            drop(unsafe { Arc::from_raw(clientd as *const CloseComplete) });
+            return Err(e);
+        }
+        Ok(Closing { client: client.clone(), state })
+    }
+
+    /// Keeps callbacks of the closed resource alive until the close has
+    /// completed. If it already has, they are dropped with the future.
+    pub(crate) fn retain(&self, callbacks: Vec<ClientData>) {
+        self.state.retained.lock().unwrap_or_else(PoisonError::into_inner).extend(callbacks);
+    }
+}
+
+impl Future for Closing {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        self.client.do_work()?;
+        if self.state.complete.load(Ordering::Acquire) {
+            Poll::Ready(Ok(()))
+        } else {
+            ctx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+unsafe extern "C" fn close_complete_trampoline(clientd: *mut c_void) {
+    let state = Arc::from_raw(clientd as *const CloseComplete);
+    drop(mem::take(&mut *state.retained.lock().unwrap_or_else(PoisonError::into_inner)));
+    state.complete.store(true, Ordering::Release);
+}
@@ -0,0 +1,388 @@
+use crate::{
+    context::{Context, ContextBuilder},
+    error::{Error, Result},
+};
+use aeron_client_sys as sys;
+use std::{env, ffi::CStr, time::Duration};
+
+const AERON_DIR: &str = env_var(sys::AERON_DIR_ENV_VAR);
+const AERON_DRIVER_TIMEOUT: &str = env_var(sys::AERON_DRIVER_TIMEOUT_ENV_VAR);
+const AERON_CLIENT_NAME: &str = env_var(sys::AERON_CLIENT_NAME_ENV_VAR);
+const AERON_RESOURCE_LINGER_DURATION: &str = env_var(sys::AERON_RESOURCE_LINGER_DURATION_ENV_VAR);
+const AERON_CLIENT_IDLE_SLEEP_DURATION: &str =
+    env_var(sys::AERON_CLIENT_IDLE_SLEEP_DURATION_ENV_VAR);
+const AERON_PRE_TOUCH_MAPPED_MEMORY: &str = env_var(sys::AERON_PRE_TOUCH_MAPPED_MEMORY_ENV_VAR);
+// `aeronc.h` has no variables for these settings, so they are named like the
+// ones it has.
+const AERON_KEEPALIVE_INTERVAL: &str = "AERON_KEEPALIVE_INTERVAL";
+const AERON_CLIENT_USE_CONDUCTOR_AGENT_INVOKER: &str = "AERON_CLIENT_USE_CONDUCTOR_AGENT_INVOKER";
+
+/// The variable name of an `*_ENV_VAR` define, which bindgen turns into a NUL
+/// terminated byte string.
+const fn env_var(name: &'static [u8]) -> &'static str {
+    match CStr::from_bytes_with_nul(name) {
+        Ok(name) => match name.to_str() {
+            Ok(name) => name,
+            Err(_) => panic!("variable name is not UTF-8"),
+        },
+        Err(_) => panic!("variable name is not NUL terminated"),
+    }
+}
+
+/// Deployment configuration for a [`Context`].
+///
+/// Loaded from the standard `AERON_*` environment variables with
+/// [`ContextConfig::from_env`] or, with the `serde` feature, deserialized from a
+/// config section. Durations in a config section are either integers in
+/// nanoseconds (milliseconds for `driver_timeout`) or strings with a `ns`, `us`,
+/// `ms` or `s` suffix, the same format the environment variables use. Unknown
+/// keys are rejected.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(deny_unknown_fields))]
+pub struct ContextConfig {
+    pub dir: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "serde_impl::millis"))]
+    pub driver_timeout: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "serde_impl::nanos"))]
+    pub keepalive_interval: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "serde_impl::nanos"))]
+    pub resource_linger_duration: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "serde_impl::nanos"))]
+    pub idle_sleep_duration: Option<Duration>,
+    pub pre_touch_mapped_memory: Option<bool>,
+    pub client_name: Option<String>,
+    pub use_conductor_agent_invoker: Option<bool>,
+}
+
+impl ContextConfig {
+    /// Reads the `AERON_*` variables the C client understands, plus
+    /// `AERON_KEEPALIVE_INTERVAL` and `AERON_CLIENT_USE_CONDUCTOR_AGENT_INVOKER`.
+    /// Unset variables are left as `None`; malformed values are reported as
+    /// [`Error::InvalidConfig`].
+    pub fn from_env() -> Result<Self> {
+        Self::from_lookup(|key| match env::var(key) {
+            Ok(value) => Ok(Some(value)),
+            Err(env::VarError::NotPresent) => Ok(None),
+            Err(env::VarError::NotUnicode(_)) => Err(invalid(key, "not valid unicode")),
+        })
+    }
+
+    fn from_lookup<L>(lookup: L) -> Result<Self>
+    where
+        L: Fn(&str) -> Result<Option<String>>,
+    {
+        let duration = |key, unit| -> Result<Option<Duration>> {
+            lookup(key)?
+                .map(|v| parse_duration(&v, unit).ok_or_else(|| invalid(key, v)))
+                .transpose()
+        };
+        let boolean = |key| -> Result<Option<bool>> {
+            lookup(key)?.map(|v| parse_bool(&v).ok_or_else(|| invalid(key, v))).transpose()
+        };
+        Ok(ContextConfig {
+            dir: lookup(AERON_DIR)?,
+            driver_timeout: duration(AERON_DRIVER_TIMEOUT, Duration::from_millis(1))?,
+            keepalive_interval: duration(AERON_KEEPALIVE_INTERVAL, Duration::from_nanos(1))?,
+            resource_linger_duration: duration(
+                AERON_RESOURCE_LINGER_DURATION,
+                Duration::from_nanos(1),
+            )?,
+            idle_sleep_duration: duration(
+                AERON_CLIENT_IDLE_SLEEP_DURATION,
+                Duration::from_nanos(1),
+            )?,
+            pre_touch_mapped_memory: boolean(AERON_PRE_TOUCH_MAPPED_MEMORY)?,
+            client_name: lookup(AERON_CLIENT_NAME)?,
+            use_conductor_agent_invoker: boolean(AERON_CLIENT_USE_CONDUCTOR_AGENT_INVOKER)?,
+        })
+    }
+
+    pub fn build(self) -> Result<Context> {
+        ContextBuilder::from(self).build()
+    }
+}
+
+impl From<ContextConfig> for ContextBuilder {
+    fn from(config: ContextConfig) -> Self {
+        let mut builder = ContextBuilder::default();
+        if let Some(dir) = config.dir {
+            builder = builder.dir(dir);
+        }
+        if let Some(timeout) = config.driver_timeout {
+            builder = builder.driver_timeout(timeout);
+        }
+        if let Some(interval) = config.keepalive_interval {
+            builder = builder.keepalive_interval(interval);
+        }
+        if let Some(duration) = config.resource_linger_duration {
+            builder = builder.resource_linger_duration(duration);
+        }
+        if let Some(duration) = config.idle_sleep_duration {
+            builder = builder.idle_sleep_duration(duration);
+        }
+        if let Some(pre_touch) = config.pre_touch_mapped_memory {
+            builder = builder.pre_touch_mapped_memory(pre_touch);
+        }
+        if let Some(name) = config.client_name {
+            builder = builder.client_name(name);
+        }
+        if let Some(use_invoker) = config.use_conductor_agent_invoker {
+            builder = builder.use_conductor_agent_invoker(use_invoker);
+        }
+        builder
+    }
+}
+
+fn invalid(key: &str, reason: impl Into<String>) -> Error {
+    Error::InvalidConfig { key: key.to_owned(), reason: reason.into() }
+}
+
+/// Parses a duration the way `aeron_parse_duration_ns` does: an integer with
+/// an optional `ns`, `us`, `ms` or `s` suffix. Values without a suffix are in
+/// `unit`.
+fn parse_duration(value: &str, unit: Duration) -> Option<Duration> {
+    let value = value.trim().to_ascii_lowercase();
+    let (digits, unit) = if let Some(v) = value.strip_suffix("ns") {
+        (v, Duration::from_nanos(1))
+    } else if let Some(v) = value.strip_suffix("us") {
+        (v, Duration::from_micros(1))
+    } else if let Some(v) = value.strip_suffix("ms") {
+        (v, Duration::from_millis(1))
+    } else if let Some(v) = value.strip_suffix('s') {
+        (v, Duration::from_secs(1))
+    } else {
+        (value.as_str(), unit)
+    };
+    scale(digits.trim().parse().ok()?, unit)
+}
+
+fn scale(value: u64, unit: Duration) -> Option<Duration> {
+    let nanos = value.checked_mul(u64::try_from(unit.as_nanos()).ok()?)?;
+    Some(Duration::from_nanos(nanos))
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "on" | "true" => Some(true),
+        "0" | "off" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use super::{parse_duration, scale};
+    use serde::{de, Deserialize, Deserializer};
+    use std::time::Duration;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Value {
+        Integer(u64),
+        Text(String),
+    }
+
+    fn duration<'de, D>(deserializer: D, unit: Duration) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<Value>::deserialize(deserializer)? {
+            None => Ok(None),
+            Some(Value::Integer(n)) => scale(n, unit)
+                .map(Some)
+                .ok_or_else(|| de::Error::custom(format!("duration {n} out of range"))),
+            Some(Value::Text(s)) => parse_duration(&s, unit)
+                .map(Some)
+                .ok_or_else(|| de::Error::custom(format!("invalid duration {s:?}"))),
+        }
+    }
+
+    pub(super) fn millis<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        duration(deserializer, Duration::from_millis(1))
+    }
+
+    pub(super) fn nanos<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        duration(deserializer, Duration::from_nanos(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn from_vars(vars: &[(&str, &str)]) -> Result<ContextConfig> {
+        let vars: HashMap<_, _> = vars.iter().copied().collect();
+        ContextConfig::from_lookup(|key| Ok(vars.get(key).map(|v| v.to_string())))
+    }
+
+    #[test]
+    fn parses_durations() {
+        let ms = Duration::from_millis(1);
+        let cases = [
+            ("10", ms, Some(Duration::from_millis(10))),
+            ("10ns", ms, Some(Duration::from_nanos(10))),
+            ("10us", ms, Some(Duration::from_micros(10))),
+            ("10ms", ms, Some(Duration::from_millis(10))),
+            ("10s", ms, Some(Duration::from_secs(10))),
+            (" 10MS ", ms, Some(Duration::from_millis(10))),
+            ("10", Duration::from_nanos(1), Some(Duration::from_nanos(10))),
+            ("0", ms, Some(Duration::ZERO)),
+            ("", ms, None),
+            ("ms", ms, None),
+            ("-1", ms, None),
+            ("1.5s", ms, None),
+            ("10m", ms, None),
+            ("18446744073709551615s", ms, None),
+        ];
+        for (value, unit, expected) in cases {
+            assert_eq!(parse_duration(value, unit), expected, "{value:?}");
+        }
+    }
+
+    #[test]
+    fn parses_bools() {
+        let cases = [
+            ("1", Some(true)),
+            ("on", Some(true)),
+            ("TRUE", Some(true)),
+            ("0", Some(false)),
+            ("off", Some(false)),
+            (" false ", Some(false)),
+            ("yes", None),
+            ("", None),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(parse_bool(value), expected, "{value:?}");
+        }
+    }
+
+    #[test]
+    fn names_variables_like_the_c_client() {
+        let names = [
+            AERON_DIR,
+            AERON_DRIVER_TIMEOUT,
+            AERON_CLIENT_NAME,
+            AERON_RESOURCE_LINGER_DURATION,
+            AERON_CLIENT_IDLE_SLEEP_DURATION,
+            AERON_PRE_TOUCH_MAPPED_MEMORY,
+        ];
+        assert_eq!(
+            names,
+            [
+                "AERON_DIR",
+                "AERON_DRIVER_TIMEOUT",
+                "AERON_CLIENT_NAME",
+                "AERON_RESOURCE_LINGER_DURATION",
+                "AERON_CLIENT_IDLE_SLEEP_DURATION",
+                "AERON_PRE_TOUCH_MAPPED_MEMORY",
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_every_variable() {
+        let config = from_vars(&[
+            (AERON_DIR, "/dev/shm/aeron"),
+            (AERON_DRIVER_TIMEOUT, "5000"),
+            (AERON_KEEPALIVE_INTERVAL, "500ms"),
+            (AERON_RESOURCE_LINGER_DURATION, "3s"),
+            (AERON_CLIENT_IDLE_SLEEP_DURATION, "16000"),
+            (AERON_PRE_TOUCH_MAPPED_MEMORY, "true"),
+            (AERON_CLIENT_NAME, "test"),
+            (AERON_CLIENT_USE_CONDUCTOR_AGENT_INVOKER, "1"),
+        ])
+        .unwrap();
+        assert_eq!(
+            config,
+            ContextConfig {
+                dir: Some("/dev/shm/aeron".to_owned()),
+                driver_timeout: Some(Duration::from_secs(5)),
+                keepalive_interval: Some(Duration::from_millis(500)),
+                resource_linger_duration: Some(Duration::from_secs(3)),
+                idle_sleep_duration: Some(Duration::from_micros(16)),
+                pre_touch_mapped_memory: Some(true),
+                client_name: Some("test".to_owned()),
+                use_conductor_agent_invoker: Some(true),
+            }
+        );
+    }
+
+    #[test]
+    fn leaves_unset_variables_empty() {
+        assert_eq!(from_vars(&[]).unwrap(), ContextConfig::default());
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        let cases = [
+            (AERON_DRIVER_TIMEOUT, "soon"),
+            (AERON_KEEPALIVE_INTERVAL, "-1ms"),
+            (AERON_RESOURCE_LINGER_DURATION, "1h"),
+            (AERON_CLIENT_IDLE_SLEEP_DURATION, ""),
+            (AERON_PRE_TOUCH_MAPPED_MEMORY, "maybe"),
+            (AERON_CLIENT_USE_CONDUCTOR_AGENT_INVOKER, "2"),
+        ];
+        for (key, value) in cases {
+            match from_vars(&[(key, value)]) {
+                Err(Error::InvalidConfig { key: k, reason }) => {
+                    assert_eq!((k.as_str(), reason.as_str()), (key, value));
+                }
+                other => panic!("{key}={value:?}: {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn propagates_lookup_errors() {
+        let result = ContextConfig::from_lookup(|key| Err(invalid(key, "not valid unicode")));
+        assert!(matches!(result, Err(Error::InvalidConfig { key, .. }) if key == AERON_DIR));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserializes_config_sections() {
+        let config: ContextConfig = serde_json::from_str(
+            r#"{
+                "dir": "/dev/shm/aeron",
+                "driver_timeout": 5000,
+                "keepalive_interval": "500ms",
+                "resource_linger_duration": 3000000000,
+                "idle_sleep_duration": null,
+                "pre_touch_mapped_memory": false
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            ContextConfig {
+                dir: Some("/dev/shm/aeron".to_owned()),
+                driver_timeout: Some(Duration::from_secs(5)),
+                keepalive_interval: Some(Duration::from_millis(500)),
+                resource_linger_duration: Some(Duration::from_secs(3)),
+                pre_touch_mapped_memory: Some(false),
+                ..ContextConfig::default()
+            }
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn rejects_invalid_config_sections() {
+        let cases = [
+            r#"{"driver_timeout": "soon"}"#,
+            r#"{"keepalive_interval": -1}"#,
+            r#"{"driver_timeout": 18446744073709551615}"#,
+            r#"{"unknown": 1}"#,
+        ];
+        for json in cases {
+            assert!(serde_json::from_str::<ContextConfig>(json).is_err(), "{json}");
+        }
+    }
+}
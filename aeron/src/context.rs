@@ -1,84 +1,380 @@
 use crate::{
-    error::{aeron_result, Error},
-    CorrelationId, SendSyncPtr, SessionId, StreamId,
+    client::{CloseReason, CloseState},
+    config::ContextConfig,
+    counters::CountersReader,
+    error::{aeron_result, Error, ErrorCode},
+    ClientData, CorrelationId, CounterId, RegistrationId, SendSyncPtr, SessionId, StreamId,
 };
 use aeron_client_sys as sys;
 use std::{
-    ffi,
-    ffi::{c_void, CStr, CString},
-    ptr,
+    ffi::{c_char, c_void, CStr, CString},
+    mem, ptr,
+    sync::Arc,
+    time::Duration,
 };
 
 pub struct Context {
     pub(crate) inner: SendSyncPtr<sys::aeron_context_t>,
+    // Callbacks registered with the C context. They are dropped after
+    // `aeron_context_close`, which `Aeron` only runs once `aeron_close` returned.
+    error_handler: Option<ClientData>,
+    on_new_publication: Option<ClientData>,
+    on_new_subscription: Option<ClientData>,
+    on_available_counter: Option<ClientData>,
+    on_unavailable_counter: Option<ClientData>,
+    on_close_client: Option<ClientData>,
+    pub(crate) close_state: Arc<CloseState>,
+    closed: bool,
 }
 
 impl Context {
+    /// Creates a context with the defaults of the C client, including its
+    /// default error handler. Set an error handler to have errors that
+    /// terminate the client resolve [`Aeron::closed`] with their reason.
+    ///
+    /// [`Aeron::closed`]: crate::client::Aeron::closed
     pub fn new() -> Result<Self, Error> {
         let mut inner = ptr::null_mut();
         aeron_result(unsafe { sys::aeron_context_init(&mut inner) })?;
-        Ok(Context { inner: inner.into() })
+        let mut context = Context {
+            inner: inner.into(),
+            error_handler: None,
+            on_new_publication: None,
+            on_new_subscription: None,
+            on_available_counter: None,
+            on_unavailable_counter: None,
+            on_close_client: None,
+            close_state: Arc::new(CloseState::default()),
+            closed: false,
+        };
+        context.set_on_close_client(|| {});
+        Ok(context)
     }
 
-    pub fn set_error_handler<F>(&self, error_handler: F)
+    /// Called for errors raised by the client conductor. Errors that terminate
+    /// the client also resolve [`Aeron::closed`](crate::client::Aeron::closed).
+    pub fn set_error_handler<F>(&mut self, error_handler: F)
     where
-        F: for<'a> FnMut(ErrorEvent<'a>),
+        F: for<'a> FnMut(ErrorEvent<'a>) + Send + 'static,
     {
-        let mut closure = error_handler;
+        let closure = ClientData::new(ErrorHandler {
+            close_state: self.close_state.clone(),
+            handler: error_handler,
+        });
         unsafe {
             sys::aeron_context_set_error_handler(
                 self.inner.as_ptr(),
                 Some(error_handler_trampoline::<F>),
-                &mut closure as *mut _ as *mut ffi::c_void,
+                closure.as_ptr(),
             )
         };
+        self.error_handler = Some(closure);
     }
 
     pub fn set_on_new_publication<F>(&mut self, on_new_publication: F)
     where
-        F: for<'a> FnMut(NewPublication<'a>),
+        F: for<'a> FnMut(NewPublication<'a>) + Send + 'static,
     {
-        let mut closure = on_new_publication;
+        let closure = ClientData::new(on_new_publication);
         unsafe {
             sys::aeron_context_set_on_new_publication(
                 self.inner.as_ptr(),
                 Some(on_new_publication_trampoline::<F>),
-                &mut closure as *mut _ as *mut ffi::c_void,
+                closure.as_ptr(),
             )
         };
+        self.on_new_publication = Some(closure);
     }
 
     pub fn set_on_new_subscription<F>(&mut self, on_new_subscription: F)
     where
-        F: for<'a> FnMut(NewSubscription<'a>),
+        F: for<'a> FnMut(NewSubscription<'a>) + Send + 'static,
     {
-        let mut closure = on_new_subscription;
+        let closure = ClientData::new(on_new_subscription);
         unsafe {
             sys::aeron_context_set_on_new_subscription(
                 self.inner.as_ptr(),
                 Some(on_new_subscription_trampoline::<F>),
-                &mut closure as *mut _ as *mut ffi::c_void,
+                closure.as_ptr(),
+            )
+        };
+        self.on_new_subscription = Some(closure);
+    }
+
+    /// Called when the driver or another client creates a counter.
+    pub fn set_on_available_counter<F>(&mut self, on_available_counter: F)
+    where
+        F: for<'a> FnMut(CounterEvent<'a>) + Send + 'static,
+    {
+        let closure = ClientData::new(on_available_counter);
+        unsafe {
+            sys::aeron_context_set_on_available_counter(
+                self.inner.as_ptr(),
+                Some(counter_trampoline::<F>),
+                closure.as_ptr(),
             )
         };
+        self.on_available_counter = Some(closure);
+    }
+
+    /// Called when a counter is removed.
+    pub fn set_on_unavailable_counter<F>(&mut self, on_unavailable_counter: F)
+    where
+        F: for<'a> FnMut(CounterEvent<'a>) + Send + 'static,
+    {
+        let closure = ClientData::new(on_unavailable_counter);
+        unsafe {
+            sys::aeron_context_set_on_unavailable_counter(
+                self.inner.as_ptr(),
+                Some(counter_trampoline::<F>),
+                closure.as_ptr(),
+            )
+        };
+        self.on_unavailable_counter = Some(closure);
+    }
+
+    /// Called once the client has closed, whether by the application or
+    /// because the conductor terminated.
+    pub fn set_on_close_client<F>(&mut self, on_close_client: F)
+    where
+        F: FnMut() + Send + 'static,
+    {
+        let closure = ClientData::new(OnCloseClient {
+            close_state: self.close_state.clone(),
+            handler: on_close_client,
+        });
+        unsafe {
+            sys::aeron_context_set_on_close_client(
+                self.inner.as_ptr(),
+                Some(on_close_client_trampoline::<F>),
+                closure.as_ptr(),
+            )
+        };
+        self.on_close_client = Some(closure);
+    }
+
+    pub fn builder() -> ContextBuilder {
+        ContextBuilder::default()
+    }
+
+    /// Builds a context from the standard `AERON_*` environment variables,
+    /// rejecting malformed values. See [`ContextConfig::from_env`].
+    ///
+    /// [`ContextConfig::from_env`]: crate::config::ContextConfig::from_env
+    pub fn from_env() -> Result<Self, Error> {
+        ContextConfig::from_env()?.build()
+    }
+
+    pub fn set_dir(&mut self, dir: &str) -> Result<(), Error> {
+        let dir = CString::new(dir)?;
+        aeron_result(unsafe { sys::aeron_context_set_dir(self.inner.as_ptr(), dir.as_ptr()) })
     }
 
     pub fn get_dir(&self) -> String {
-        let dir = unsafe { sys::aeron_context_get_dir(self.inner.as_ptr()) };
-        if !dir.is_null() {
-            unsafe {
-                let cs = CStr::from_ptr(dir as *mut i8);
-                CString::from(cs).into_string().expect("string")
-            }
-        } else {
-            "".to_owned()
+        string_from_ptr(unsafe { sys::aeron_context_get_dir(self.inner.as_ptr()) })
+    }
+
+    pub fn set_driver_timeout(&mut self, timeout: Duration) -> Result<(), Error> {
+        let ms = u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX);
+        aeron_result(unsafe { sys::aeron_context_set_driver_timeout_ms(self.inner.as_ptr(), ms) })
+    }
+
+    pub fn get_driver_timeout(&self) -> Duration {
+        Duration::from_millis(unsafe {
+            sys::aeron_context_get_driver_timeout_ms(self.inner.as_ptr())
+        })
+    }
+
+    pub fn set_keepalive_interval(&mut self, interval: Duration) -> Result<(), Error> {
+        aeron_result(unsafe {
+            sys::aeron_context_set_keepalive_interval_ns(self.inner.as_ptr(), as_nanos(interval))
+        })
+    }
+
+    pub fn get_keepalive_interval(&self) -> Duration {
+        Duration::from_nanos(unsafe {
+            sys::aeron_context_get_keepalive_interval_ns(self.inner.as_ptr())
+        })
+    }
+
+    pub fn set_resource_linger_duration(&mut self, duration: Duration) -> Result<(), Error> {
+        aeron_result(unsafe {
+            sys::aeron_context_set_resource_linger_duration_ns(
+                self.inner.as_ptr(),
+                as_nanos(duration),
+            )
+        })
+    }
+
+    pub fn get_resource_linger_duration(&self) -> Duration {
+        Duration::from_nanos(unsafe {
+            sys::aeron_context_get_resource_linger_duration_ns(self.inner.as_ptr())
+        })
+    }
+
+    pub fn set_idle_sleep_duration(&mut self, duration: Duration) -> Result<(), Error> {
+        aeron_result(unsafe {
+            sys::aeron_context_set_idle_sleep_duration_ns(self.inner.as_ptr(), as_nanos(duration))
+        })
+    }
+
+    pub fn get_idle_sleep_duration(&self) -> Duration {
+        Duration::from_nanos(unsafe {
+            sys::aeron_context_get_idle_sleep_duration_ns(self.inner.as_ptr())
+        })
+    }
+
+    pub fn set_pre_touch_mapped_memory(&mut self, pre_touch: bool) -> Result<(), Error> {
+        aeron_result(unsafe {
+            sys::aeron_context_set_pre_touch_mapped_memory(self.inner.as_ptr(), pre_touch)
+        })
+    }
+
+    pub fn get_pre_touch_mapped_memory(&self) -> bool {
+        unsafe { sys::aeron_context_get_pre_touch_mapped_memory(self.inner.as_ptr()) }
+    }
+
+    pub fn set_client_name(&mut self, name: &str) -> Result<(), Error> {
+        let name = CString::new(name)?;
+        aeron_result(unsafe {
+            sys::aeron_context_set_client_name(self.inner.as_ptr(), name.as_ptr())
+        })
+    }
+
+    pub fn get_client_name(&self) -> String {
+        string_from_ptr(unsafe { sys::aeron_context_get_client_name(self.inner.as_ptr()) })
+    }
+
+    /// Runs the client conductor on the caller's thread instead of a background
+    /// thread. The application then drives it with [`Aeron::do_work`] or
+    /// [`Aeron::invoke`].
+    ///
+    /// [`Aeron::do_work`]: crate::client::Aeron::do_work
+    /// [`Aeron::invoke`]: crate::client::Aeron::invoke
+    pub fn set_use_conductor_agent_invoker(&mut self, use_invoker: bool) -> Result<(), Error> {
+        aeron_result(unsafe {
+            sys::aeron_context_set_use_conductor_agent_invoker(self.inner.as_ptr(), use_invoker)
+        })
+    }
+
+    pub fn get_use_conductor_agent_invoker(&self) -> bool {
+        unsafe { sys::aeron_context_get_use_conductor_agent_invoker(self.inner.as_ptr()) }
+    }
+
+    /// Closes the context and reports whether the C context was released.
+    /// Dropping the context closes it as well, but ignores errors.
+    pub fn close(mut self) -> Result<(), Error> {
+        self.close_in_place()
+    }
+
+    pub(crate) fn close_in_place(&mut self) -> Result<(), Error> {
+        if mem::replace(&mut self.closed, true) {
+            return Ok(());
         }
+        aeron_result(unsafe { sys::aeron_context_close(self.inner.as_ptr()) })
     }
 }
 
 impl Drop for Context {
     fn drop(&mut self) {
-        // TODO: err
-        aeron_result(unsafe { sys::aeron_context_close(self.inner.as_ptr()) }).ok();
+        self.close_in_place().ok();
+    }
+}
+
+/// Collects configuration for a [`Context`]. Unset options keep the defaults of
+/// the C client, which also honours the `AERON_*` environment variables.
+#[derive(Debug, Clone, Default)]
+pub struct ContextBuilder {
+    dir: Option<String>,
+    driver_timeout: Option<Duration>,
+    keepalive_interval: Option<Duration>,
+    resource_linger_duration: Option<Duration>,
+    idle_sleep_duration: Option<Duration>,
+    pre_touch_mapped_memory: Option<bool>,
+    client_name: Option<String>,
+    use_conductor_agent_invoker: Option<bool>,
+}
+
+impl ContextBuilder {
+    pub fn dir(mut self, dir: impl Into<String>) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    pub fn driver_timeout(mut self, timeout: Duration) -> Self {
+        self.driver_timeout = Some(timeout);
+        self
+    }
+
+    pub fn keepalive_interval(mut self, interval: Duration) -> Self {
+        self.keepalive_interval = Some(interval);
+        self
+    }
+
+    pub fn resource_linger_duration(mut self, duration: Duration) -> Self {
+        self.resource_linger_duration = Some(duration);
+        self
+    }
+
+    pub fn idle_sleep_duration(mut self, duration: Duration) -> Self {
+        self.idle_sleep_duration = Some(duration);
+        self
+    }
+
+    pub fn pre_touch_mapped_memory(mut self, pre_touch: bool) -> Self {
+        self.pre_touch_mapped_memory = Some(pre_touch);
+        self
+    }
+
+    pub fn client_name(mut self, name: impl Into<String>) -> Self {
+        self.client_name = Some(name.into());
+        self
+    }
+
+    pub fn use_conductor_agent_invoker(mut self, use_invoker: bool) -> Self {
+        self.use_conductor_agent_invoker = Some(use_invoker);
+        self
+    }
+
+    pub fn build(self) -> Result<Context, Error> {
+        let mut context = Context::new()?;
+        if let Some(dir) = &self.dir {
+            context.set_dir(dir)?;
+        }
+        if let Some(timeout) = self.driver_timeout {
+            context.set_driver_timeout(timeout)?;
+        }
+        if let Some(interval) = self.keepalive_interval {
+            context.set_keepalive_interval(interval)?;
+        }
+        if let Some(duration) = self.resource_linger_duration {
+            context.set_resource_linger_duration(duration)?;
+        }
+        if let Some(duration) = self.idle_sleep_duration {
+            context.set_idle_sleep_duration(duration)?;
+        }
+        if let Some(pre_touch) = self.pre_touch_mapped_memory {
+            context.set_pre_touch_mapped_memory(pre_touch)?;
+        }
+        if let Some(name) = &self.client_name {
+            context.set_client_name(name)?;
+        }
+        if let Some(use_invoker) = self.use_conductor_agent_invoker {
+            context.set_use_conductor_agent_invoker(use_invoker)?;
+        }
+        Ok(context)
+    }
+}
+
+fn as_nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+pub(crate) fn string_from_ptr(ptr: *const c_char) -> String {
+    if !ptr.is_null() {
+        unsafe { CStr::from_ptr(ptr) }.to_string_lossy().into_owned()
+    } else {
+        "".to_owned()
     }
 }
 
@@ -89,6 +385,12 @@ pub struct ErrorEvent<'a> {
     pub message: &'a str,
 }
 
+impl ErrorEvent<'_> {
+    pub fn error_code(&self) -> ErrorCode {
+        ErrorCode::from_raw(self.code)
+    }
+}
+
 #[derive(Debug, Clone)]
 #[non_exhaustive]
 pub struct NewPublication<'a> {
@@ -106,6 +408,19 @@ pub struct NewSubscription<'a> {
     pub correlation_id: CorrelationId,
 }
 
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct CounterEvent<'a> {
+    pub counters_reader: CountersReader<'a>,
+    pub registration_id: RegistrationId,
+    pub counter_id: CounterId,
+}
+
+struct ErrorHandler<F> {
+    close_state: Arc<CloseState>,
+    handler: F,
+}
+
 unsafe extern "C" fn error_handler_trampoline<F>(
     clientd: *mut c_void,
     code: i32,
@@ -114,8 +429,33 @@ unsafe extern "C" fn error_handler_trampoline<F>(
     F: for<'a> FnMut(ErrorEvent<'a>),
 {
     let message = &*CStr::from_ptr(message).to_string_lossy();
-    let closure = &mut *(clientd as *mut F);
-    closure(ErrorEvent { code, message })
+    let closure = &mut *(clientd as *mut ErrorHandler<F>);
+    match ErrorCode::from_raw(code) {
+        ErrorCode::DriverTimeout => {
+            closure.close_state.fatal_error(CloseReason::DriverTimeout(message.to_owned()));
+        }
+        error_code if error_code.is_fatal() => {
+            closure
+                .close_state
+                .fatal_error(CloseReason::ConductorError { code, message: message.to_owned() });
+        }
+        _ => {}
+    }
+    (closure.handler)(ErrorEvent { code, message })
+}
+
+struct OnCloseClient<F> {
+    close_state: Arc<CloseState>,
+    handler: F,
+}
+
+unsafe extern "C" fn on_close_client_trampoline<F>(clientd: *mut c_void)
+where
+    F: FnMut(),
+{
+    let closure = &mut *(clientd as *mut OnCloseClient<F>);
+    closure.close_state.client_closed();
+    (closure.handler)()
 }
 
 unsafe extern "C" fn on_new_publication_trampoline<F>(
@@ -155,3 +495,19 @@ unsafe extern "C" fn on_new_subscription_trampoline<F>(
         correlation_id: CorrelationId(correlation_id),
     });
 }
+
+unsafe extern "C" fn counter_trampoline<F>(
+    clientd: *mut c_void,
+    counters_reader: *mut sys::aeron_counters_reader_t,
+    registration_id: i64,
+    counter_id: i32,
+) where
+    F: for<'a> FnMut(CounterEvent<'a>),
+{
+    let closure = &mut *(clientd as *mut F);
+    closure(CounterEvent {
+        counters_reader: CountersReader::new(counters_reader),
+        registration_id: RegistrationId(registration_id),
+        counter_id: CounterId(counter_id),
+    });
+}
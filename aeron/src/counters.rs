@@ -0,0 +1,102 @@
+use crate::{
+    error::{aeron_error, Result},
+    CounterId, RegistrationId, SendSyncPtr,
+};
+use aeron_client_sys as sys;
+use std::{
+    fmt,
+    marker::PhantomData,
+    sync::atomic::{AtomicI64, Ordering},
+};
+
+// Matches AERON_COUNTER_MAX_LABEL_LENGTH plus room for the terminating NUL.
+const MAX_LABEL_LENGTH: usize = 381;
+
+/// Read-only view of the counters in the CnC file. Borrowed from the client
+/// that owns it.
+pub struct CountersReader<'a> {
+    inner: SendSyncPtr<sys::aeron_counters_reader_t>,
+    _client: PhantomData<&'a ()>,
+}
+
+impl CountersReader<'_> {
+    pub(crate) fn new(inner: *mut sys::aeron_counters_reader_t) -> Self {
+        CountersReader { inner: inner.into(), _client: PhantomData }
+    }
+
+    pub fn max_counter_id(&self) -> CounterId {
+        CounterId(unsafe { sys::aeron_counters_reader_max_counter_id(self.inner.as_ptr()) })
+    }
+
+    /// Current value of the counter, or `None` if the id is out of range.
+    pub fn counter_value(&self, counter_id: CounterId) -> Option<i64> {
+        if counter_id.0 < 0 || counter_id.0 > self.max_counter_id().0 {
+            return None;
+        }
+        let addr = unsafe { sys::aeron_counters_reader_addr(self.inner.as_ptr(), counter_id.0) };
+        if addr.is_null() {
+            return None;
+        }
+        // Counters are updated concurrently by the driver and other clients.
+        Some(unsafe { AtomicI64::from_ptr(addr) }.load(Ordering::Acquire))
+    }
+
+    pub fn counter_registration_id(&self, counter_id: CounterId) -> Result<RegistrationId> {
+        let mut registration_id = 0;
+        let res = unsafe {
+            sys::aeron_counters_reader_counter_registration_id(
+                self.inner.as_ptr(),
+                counter_id.0,
+                &mut registration_id,
+            )
+        };
+        if res < 0 {
+            return Err(aeron_error());
+        }
+        Ok(RegistrationId(registration_id))
+    }
+
+    pub fn counter_type_id(&self, counter_id: CounterId) -> Result<i32> {
+        let mut type_id = 0;
+        let res = unsafe {
+            sys::aeron_counters_reader_counter_type_id(
+                self.inner.as_ptr(),
+                counter_id.0,
+                &mut type_id,
+            )
+        };
+        if res < 0 {
+            return Err(aeron_error());
+        }
+        Ok(type_id)
+    }
+
+    pub fn counter_label(&self, counter_id: CounterId) -> Result<String> {
+        let mut buffer = [0u8; MAX_LABEL_LENGTH];
+        let res = unsafe {
+            sys::aeron_counters_reader_counter_label(
+                self.inner.as_ptr(),
+                counter_id.0,
+                buffer.as_mut_ptr() as *mut _,
+                buffer.len(),
+            )
+        };
+        if res < 0 {
+            return Err(aeron_error());
+        }
+        let length = (res as usize).min(buffer.len());
+        Ok(String::from_utf8_lossy(&buffer[..length]).into_owned())
+    }
+}
+
+impl Clone for CountersReader<'_> {
+    fn clone(&self) -> Self {
+        CountersReader::new(self.inner.as_ptr())
+    }
+}
+
+impl fmt::Debug for CountersReader<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CountersReader").field("inner", &self.inner.as_ptr()).finish()
+    }
+}
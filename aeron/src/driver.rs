@@ -0,0 +1,222 @@
+use crate::{
+    context::string_from_ptr,
+    error::{aeron_result, Result},
+    SendSyncPtr,
+};
+use aeron_client_sys as sys;
+use std::{ffi::CString, mem, ptr, time::Duration};
+
+/// A C media driver running inside the process.
+///
+/// The driver runs on its own threads from [`MediaDriverBuilder::launch`]
+/// until it is closed or dropped.
+pub struct MediaDriver {
+    inner: SendSyncPtr<sys::aeron_driver_t>,
+    context: DriverContext,
+    closed: bool,
+}
+
+impl MediaDriver {
+    pub fn builder() -> MediaDriverBuilder {
+        MediaDriverBuilder::default()
+    }
+
+    /// Launches a driver with the defaults of the C driver, which also honours
+    /// the `AERON_*` environment variables.
+    pub fn launch() -> Result<Self> {
+        MediaDriverBuilder::default().launch()
+    }
+
+    /// The `aeron.dir` clients have to use to connect to this driver.
+    pub fn dir(&self) -> String {
+        string_from_ptr(unsafe { sys::aeron_driver_context_get_dir(self.context.inner.as_ptr()) })
+    }
+
+    /// Stops the driver and then closes its context, and reports whether both
+    /// were released. Dropping the driver closes it as well, but ignores
+    /// errors.
+    pub fn close(mut self) -> Result<()> {
+        self.close_in_place()?;
+        self.context.close_in_place()
+    }
+
+    fn close_in_place(&mut self) -> Result<()> {
+        if mem::replace(&mut self.closed, true) {
+            return Ok(());
+        }
+        aeron_result(unsafe { sys::aeron_driver_close(self.inner.as_ptr()) })
+    }
+}
+
+impl Drop for MediaDriver {
+    fn drop(&mut self) {
+        self.close_in_place().ok();
+    }
+}
+
+struct DriverContext {
+    inner: SendSyncPtr<sys::aeron_driver_context_t>,
+    closed: bool,
+}
+
+impl DriverContext {
+    fn close_in_place(&mut self) -> Result<()> {
+        if mem::replace(&mut self.closed, true) {
+            return Ok(());
+        }
+        aeron_result(unsafe { sys::aeron_driver_context_close(self.inner.as_ptr()) })
+    }
+}
+
+impl Drop for DriverContext {
+    fn drop(&mut self) {
+        self.close_in_place().ok();
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ThreadingMode {
+    Dedicated = sys::aeron_threading_mode_enum_AERON_THREADING_MODE_DEDICATED,
+    SharedNetwork = sys::aeron_threading_mode_enum_AERON_THREADING_MODE_SHARED_NETWORK,
+    Shared = sys::aeron_threading_mode_enum_AERON_THREADING_MODE_SHARED,
+}
+
+/// Collects configuration for a [`MediaDriver`]. Unset options keep the
+/// defaults of the C driver.
+#[derive(Debug, Clone, Default)]
+pub struct MediaDriverBuilder {
+    dir: Option<String>,
+    dir_delete_on_start: Option<bool>,
+    dir_delete_on_shutdown: Option<bool>,
+    threading_mode: Option<ThreadingMode>,
+    driver_timeout: Option<Duration>,
+    client_liveness_timeout: Option<Duration>,
+    term_buffer_length: Option<usize>,
+    ipc_term_buffer_length: Option<usize>,
+    mtu_length: Option<usize>,
+    term_buffer_sparse_file: Option<bool>,
+    print_configuration: Option<bool>,
+}
+
+impl MediaDriverBuilder {
+    pub fn dir(mut self, dir: impl Into<String>) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    pub fn dir_delete_on_start(mut self, delete: bool) -> Self {
+        self.dir_delete_on_start = Some(delete);
+        self
+    }
+
+    pub fn dir_delete_on_shutdown(mut self, delete: bool) -> Self {
+        self.dir_delete_on_shutdown = Some(delete);
+        self
+    }
+
+    pub fn threading_mode(mut self, mode: ThreadingMode) -> Self {
+        self.threading_mode = Some(mode);
+        self
+    }
+
+    pub fn driver_timeout(mut self, timeout: Duration) -> Self {
+        self.driver_timeout = Some(timeout);
+        self
+    }
+
+    pub fn client_liveness_timeout(mut self, timeout: Duration) -> Self {
+        self.client_liveness_timeout = Some(timeout);
+        self
+    }
+
+    pub fn term_buffer_length(mut self, length: usize) -> Self {
+        self.term_buffer_length = Some(length);
+        self
+    }
+
+    pub fn ipc_term_buffer_length(mut self, length: usize) -> Self {
+        self.ipc_term_buffer_length = Some(length);
+        self
+    }
+
+    pub fn mtu_length(mut self, length: usize) -> Self {
+        self.mtu_length = Some(length);
+        self
+    }
+
+    pub fn term_buffer_sparse_file(mut self, sparse: bool) -> Self {
+        self.term_buffer_sparse_file = Some(sparse);
+        self
+    }
+
+    pub fn print_configuration(mut self, print: bool) -> Self {
+        self.print_configuration = Some(print);
+        self
+    }
+
+    pub fn launch(self) -> Result<MediaDriver> {
+        let mut inner = ptr::null_mut();
+        aeron_result(unsafe { sys::aeron_driver_context_init(&mut inner) })?;
+        let context = DriverContext { inner: inner.into(), closed: false };
+        let ctx = context.inner.as_ptr();
+
+        if let Some(dir) = &self.dir {
+            let dir = CString::new(dir.as_bytes())?;
+            aeron_result(unsafe { sys::aeron_driver_context_set_dir(ctx, dir.as_ptr()) })?;
+        }
+        if let Some(delete) = self.dir_delete_on_start {
+            aeron_result(unsafe {
+                sys::aeron_driver_context_set_dir_delete_on_start(ctx, delete)
+            })?;
+        }
+        if let Some(delete) = self.dir_delete_on_shutdown {
+            aeron_result(unsafe {
+                sys::aeron_driver_context_set_dir_delete_on_shutdown(ctx, delete)
+            })?;
+        }
+        if let Some(mode) = self.threading_mode {
+            aeron_result(unsafe {
+                sys::aeron_driver_context_set_threading_mode(
+                    ctx,
+                    mode as sys::aeron_threading_mode_t,
+                )
+            })?;
+        }
+        if let Some(timeout) = self.driver_timeout {
+            let ms = u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX);
+            aeron_result(unsafe { sys::aeron_driver_context_set_driver_timeout_ms(ctx, ms) })?;
+        }
+        if let Some(timeout) = self.client_liveness_timeout {
+            let ns = u64::try_from(timeout.as_nanos()).unwrap_or(u64::MAX);
+            aeron_result(unsafe {
+                sys::aeron_driver_context_set_client_liveness_timeout_ns(ctx, ns)
+            })?;
+        }
+        if let Some(length) = self.term_buffer_length {
+            aeron_result(unsafe { sys::aeron_driver_context_set_term_buffer_length(ctx, length) })?;
+        }
+        if let Some(length) = self.ipc_term_buffer_length {
+            aeron_result(unsafe {
+                sys::aeron_driver_context_set_ipc_term_buffer_length(ctx, length)
+            })?;
+        }
+        if let Some(length) = self.mtu_length {
+            aeron_result(unsafe { sys::aeron_driver_context_set_mtu_length(ctx, length) })?;
+        }
+        if let Some(sparse) = self.term_buffer_sparse_file {
+            aeron_result(unsafe {
+                sys::aeron_driver_context_set_term_buffer_sparse_file(ctx, sparse)
+            })?;
+        }
+        if let Some(print) = self.print_configuration {
+            aeron_result(unsafe { sys::aeron_driver_context_set_print_configuration(ctx, print) })?;
+        }
+
+        let mut driver = ptr::null_mut();
+        aeron_result(unsafe { sys::aeron_driver_init(&mut driver, ctx) })?;
+        let driver = MediaDriver { inner: driver.into(), context, closed: false };
+        aeron_result(unsafe { sys::aeron_driver_start(driver.inner.as_ptr(), false) })?;
+        Ok(driver)
+    }
+}
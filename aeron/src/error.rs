@@ -1,26 +1,236 @@
 use aeron_client_sys as sys;
-use std::ffi::{CStr, NulError};
+use std::{
+    ffi::{CStr, NulError},
+    fmt, io,
+};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
 pub enum Error {
-    #[error("FfiError {0}: {1}")]
-    FfiError(i32, String),
+    #[error("{code}: {message}")]
+    Aeron { code: ErrorCode, message: String },
     #[error("CString NulError: {0}")]
     NulError(#[from] NulError),
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid configuration {key}: {reason}")]
+    InvalidConfig { key: String, reason: String },
+    #[error("client is still referenced by publications, subscriptions or other handles")]
+    ClientInUse,
+    #[error("publication is not connected")]
+    NotConnected,
+    #[error("operation timed out")]
+    TimedOut,
+}
+
+impl Error {
+    /// The Aeron error code, if the error was raised by the C client.
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            Error::Aeron { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// Whether the operation may succeed if it is retried later.
+    pub fn is_retryable(&self) -> bool {
+        self.code().is_some_and(ErrorCode::is_retryable)
+    }
+}
+
+/// Error codes reported through `aeron_errcode()`.
+///
+/// Codes of the client conductor are negative, as are the error codes the
+/// media driver sends in its error responses, i.e. the negated `ErrorCode` of
+/// the Java driver. Positive codes are `errno` values; zero is not an error
+/// code and maps to [`ErrorCode::Unknown`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorCode {
+    /// The media driver did not respond within the driver timeout.
+    DriverTimeout,
+    /// The media driver timed out this client.
+    ClientTimeout,
+    /// The conductor was not invoked within the service interval.
+    ConductorServiceTimeout,
+    /// The command buffer to the media driver is full.
+    BufferFull,
+    /// The publication has been closed.
+    PublicationClosed,
+    /// The publication reached its maximum possible position.
+    MaxPositionExceeded,
+    GenericError,
+    InvalidChannel,
+    UnknownSubscription,
+    UnknownPublication,
+    ChannelEndpointError,
+    UnknownCounter,
+    UnknownCommandTypeId,
+    MalformedCommand,
+    NotSupported,
+    UnknownHost,
+    ResourceTemporarilyUnavailable,
+    StorageSpace,
+    ImageRejected,
+    PublicationRevoked,
+    /// An operating system error with the given `errno`.
+    Os(i32),
+    Unknown(i32),
+}
+
+const AERON_CLIENT_ERROR_DRIVER_TIMEOUT: i32 = -1000;
+const AERON_CLIENT_ERROR_CLIENT_TIMEOUT: i32 = -1001;
+const AERON_CLIENT_ERROR_CONDUCTOR_SERVICE_TIMEOUT: i32 = -1002;
+const AERON_CLIENT_ERROR_BUFFER_FULL: i32 = -1003;
+
+impl ErrorCode {
+    pub fn from_raw(code: i32) -> Self {
+        match code {
+            AERON_CLIENT_ERROR_DRIVER_TIMEOUT => ErrorCode::DriverTimeout,
+            AERON_CLIENT_ERROR_CLIENT_TIMEOUT => ErrorCode::ClientTimeout,
+            AERON_CLIENT_ERROR_CONDUCTOR_SERVICE_TIMEOUT => ErrorCode::ConductorServiceTimeout,
+            AERON_CLIENT_ERROR_BUFFER_FULL => ErrorCode::BufferFull,
+            -1 => ErrorCode::InvalidChannel,
+            -2 => ErrorCode::UnknownSubscription,
+            -3 => ErrorCode::UnknownPublication,
+            -4 => ErrorCode::ChannelEndpointError,
+            -5 => ErrorCode::UnknownCounter,
+            -6 => ErrorCode::UnknownCommandTypeId,
+            -7 => ErrorCode::MalformedCommand,
+            -8 => ErrorCode::NotSupported,
+            -9 => ErrorCode::UnknownHost,
+            -10 => ErrorCode::ResourceTemporarilyUnavailable,
+            -11 => ErrorCode::GenericError,
+            -12 => ErrorCode::StorageSpace,
+            -13 => ErrorCode::ImageRejected,
+            -14 => ErrorCode::PublicationRevoked,
+            errno if errno > 0 => ErrorCode::Os(errno),
+            code => ErrorCode::Unknown(code),
+        }
+    }
+
+    /// Whether the condition is transient, so that retrying the operation
+    /// later may succeed.
+    pub fn is_retryable(self) -> bool {
+        matches!(self, ErrorCode::BufferFull | ErrorCode::ResourceTemporarilyUnavailable)
+    }
+
+    /// Whether the client conductor terminated after this error.
+    pub fn is_fatal(self) -> bool {
+        matches!(
+            self,
+            ErrorCode::DriverTimeout
+                | ErrorCode::ClientTimeout
+                | ErrorCode::ConductorServiceTimeout
+        )
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorCode::Os(errno) => write!(f, "{}", io::Error::from_raw_os_error(*errno)),
+            ErrorCode::Unknown(code) => write!(f, "unknown error {code}"),
+            code => fmt::Debug::fmt(code, f),
+        }
+    }
 }
 
 pub(crate) fn aeron_result(code: i32) -> Result<()> {
-    // TODO: aeron_errmsg
     match code {
         0 => Ok(()),
-        _ => Err(aeron_error(code)),
+        _ => Err(aeron_error()),
     }
 }
 
-pub(crate) fn aeron_error(code: i32) -> Error {
-    let msg = unsafe { CStr::from_ptr(sys::aeron_errmsg()) }.to_string_lossy();
-    println!("aeron_result: {code}");
-    Error::FfiError(code, msg.to_string())
+/// Maps the fragment or byte count returned by the poll functions.
+pub(crate) fn aeron_count(count: i64) -> Result<usize> {
+    usize::try_from(count).map_err(|_| aeron_error())
+}
+
+/// Builds an error from the thread-local `aeron_errcode()` and
+/// `aeron_errmsg()` of the last failed call.
+pub(crate) fn aeron_error() -> Error {
+    let code = unsafe { sys::aeron_errcode() };
+    let message = unsafe { CStr::from_ptr(sys::aeron_errmsg()) }.to_string_lossy();
+    Error::Aeron { code: ErrorCode::from_raw(code), message: message.into_owned() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The same on every platform the C client supports.
+    const ENOENT: i32 = 2;
+
+    const CODES: [(i32, ErrorCode, &str); 18] = [
+        (-1000, ErrorCode::DriverTimeout, "DriverTimeout"),
+        (-1001, ErrorCode::ClientTimeout, "ClientTimeout"),
+        (-1002, ErrorCode::ConductorServiceTimeout, "ConductorServiceTimeout"),
+        (-1003, ErrorCode::BufferFull, "BufferFull"),
+        (-1, ErrorCode::InvalidChannel, "InvalidChannel"),
+        (-2, ErrorCode::UnknownSubscription, "UnknownSubscription"),
+        (-3, ErrorCode::UnknownPublication, "UnknownPublication"),
+        (-4, ErrorCode::ChannelEndpointError, "ChannelEndpointError"),
+        (-5, ErrorCode::UnknownCounter, "UnknownCounter"),
+        (-6, ErrorCode::UnknownCommandTypeId, "UnknownCommandTypeId"),
+        (-7, ErrorCode::MalformedCommand, "MalformedCommand"),
+        (-8, ErrorCode::NotSupported, "NotSupported"),
+        (-9, ErrorCode::UnknownHost, "UnknownHost"),
+        (-10, ErrorCode::ResourceTemporarilyUnavailable, "ResourceTemporarilyUnavailable"),
+        (-11, ErrorCode::GenericError, "GenericError"),
+        (-12, ErrorCode::StorageSpace, "StorageSpace"),
+        (-13, ErrorCode::ImageRejected, "ImageRejected"),
+        (-14, ErrorCode::PublicationRevoked, "PublicationRevoked"),
+    ];
+
+    #[test]
+    fn maps_raw_codes() {
+        for (raw, code, _) in CODES {
+            assert_eq!(ErrorCode::from_raw(raw), code, "{raw}");
+        }
+        assert_eq!(ErrorCode::from_raw(0), ErrorCode::Unknown(0));
+        assert_eq!(ErrorCode::from_raw(-15), ErrorCode::Unknown(-15));
+        assert_eq!(ErrorCode::from_raw(-999), ErrorCode::Unknown(-999));
+        assert_eq!(ErrorCode::from_raw(-1004), ErrorCode::Unknown(-1004));
+        assert_eq!(ErrorCode::from_raw(ENOENT), ErrorCode::Os(ENOENT));
+    }
+
+    #[test]
+    fn classifies_codes() {
+        for (raw, code, _) in CODES {
+            let retryable =
+                matches!(code, ErrorCode::BufferFull | ErrorCode::ResourceTemporarilyUnavailable);
+            let fatal = matches!(raw, -1002..=-1000);
+            assert_eq!(code.is_retryable(), retryable, "{code:?}");
+            assert_eq!(code.is_fatal(), fatal, "{code:?}");
+        }
+        for code in [ErrorCode::Os(ENOENT), ErrorCode::Unknown(0)] {
+            assert!(!code.is_retryable() && !code.is_fatal(), "{code:?}");
+        }
+    }
+
+    #[test]
+    fn displays_codes() {
+        for (_, code, name) in CODES {
+            assert_eq!(code.to_string(), name);
+        }
+        assert_eq!(ErrorCode::Unknown(-15).to_string(), "unknown error -15");
+        assert_eq!(
+            ErrorCode::Os(ENOENT).to_string(),
+            io::Error::from_raw_os_error(ENOENT).to_string()
+        );
+    }
+
+    #[test]
+    fn reports_codes_of_errors() {
+        let error = Error::Aeron { code: ErrorCode::BufferFull, message: "full".to_owned() };
+        assert_eq!(error.code(), Some(ErrorCode::BufferFull));
+        assert!(error.is_retryable());
+        assert_eq!(error.to_string(), "BufferFull: full");
+        assert_eq!(Error::ClientInUse.code(), None);
+        assert!(!Error::ClientInUse.is_retryable());
+    }
 }
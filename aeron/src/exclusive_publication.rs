@@ -0,0 +1,246 @@
+use crate::{
+    client::{Aeron, Closing},
+    error::{aeron_error, aeron_result, Result},
+    publication::{
+        offer_result, reserved_value_supplier_trampoline, with_iovecs, BufferClaim, OfferResult,
+    },
+    ChannelStatus, Position, SendSyncPtr, StreamId,
+};
+use aeron_client_sys as sys;
+use std::{
+    ffi,
+    ffi::CString,
+    io::IoSlice,
+    mem::MaybeUninit,
+    {future::Future, pin::Pin, sync::Arc, task::Poll},
+    {ptr, task},
+};
+
+/// A publication with a single writer. It has a session of its own, so offers
+/// skip the coordination between concurrent publishers of a [`Publication`].
+///
+/// [`Publication`]: crate::publication::Publication
+pub struct ExclusivePublication {
+    client: Arc<Aeron>,
+    inner: SendSyncPtr<sys::aeron_exclusive_publication_t>,
+    closed: bool,
+}
+
+impl ExclusivePublication {
+    fn new(client: &Arc<Aeron>, inner: *mut sys::aeron_exclusive_publication_t) -> Self {
+        ExclusivePublication { client: client.clone(), inner: inner.into(), closed: false }
+    }
+
+    /// The client this publication was added to.
+    pub fn client(&self) -> &Arc<Aeron> {
+        &self.client
+    }
+
+    pub fn channel_status(&self) -> ChannelStatus {
+        match unsafe { sys::aeron_exclusive_publication_channel_status(self.inner.as_ptr()) } {
+            1 => ChannelStatus::Active,
+            -1 => ChannelStatus::Errored,
+            v => ChannelStatus::Other(v),
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        unsafe { sys::aeron_exclusive_publication_is_connected(self.inner.as_ptr()) }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        unsafe { sys::aeron_exclusive_publication_is_closed(self.inner.as_ptr()) }
+    }
+
+    /// Closes the publication. The returned future resolves once the client
+    /// conductor has released it. Dropping the publication closes it as well,
+    /// but ignores errors.
+    pub fn close(mut self) -> Result<Closing> {
+        let inner = self.inner.as_ptr();
+        let closing = Closing::new(&self.client, |on_close_complete, clientd| unsafe {
+            sys::aeron_exclusive_publication_close(inner, on_close_complete, clientd)
+        })?;
+        self.closed = true;
+        Ok(closing)
+    }
+
+    pub fn offer(&mut self, data: &[u8]) -> Result<OfferResult> {
+        offer_result(unsafe {
+            sys::aeron_exclusive_publication_offer(
+                self.inner.as_ptr(),
+                data.as_ptr(),
+                data.len(),
+                None,
+                ptr::null_mut(),
+            )
+        })
+    }
+
+    pub fn offer_with_reserved_value_supplier<F>(
+        &mut self,
+        data: &[u8],
+        reserved_value_supplier: F,
+    ) -> Result<OfferResult>
+    where
+        F: for<'a> FnMut(&'a mut [u8]) -> i64,
+    {
+        let mut closure = reserved_value_supplier;
+        offer_result(unsafe {
+            sys::aeron_exclusive_publication_offer(
+                self.inner.as_ptr(),
+                data.as_ptr(),
+                data.len(),
+                Some(reserved_value_supplier_trampoline::<F>),
+                &mut closure as *mut _ as *mut ffi::c_void,
+            )
+        })
+    }
+
+    /// Offers the concatenation of `bufs` as a single message.
+    pub fn offer_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<OfferResult> {
+        offer_result(with_iovecs(bufs, |iov, iovcnt| unsafe {
+            sys::aeron_exclusive_publication_offerv(
+                self.inner.as_ptr(),
+                iov,
+                iovcnt,
+                None,
+                ptr::null_mut(),
+            )
+        }))
+    }
+
+    pub fn offer_vectored_with_reserved_value_supplier<F>(
+        &mut self,
+        bufs: &[IoSlice<'_>],
+        reserved_value_supplier: F,
+    ) -> Result<OfferResult>
+    where
+        F: for<'a> FnMut(&'a mut [u8]) -> i64,
+    {
+        let mut closure = reserved_value_supplier;
+        offer_result(with_iovecs(bufs, |iov, iovcnt| unsafe {
+            sys::aeron_exclusive_publication_offerv(
+                self.inner.as_ptr(),
+                iov,
+                iovcnt,
+                Some(reserved_value_supplier_trampoline::<F>),
+                &mut closure as *mut _ as *mut ffi::c_void,
+            )
+        }))
+    }
+
+    pub fn try_claim(&mut self, length: usize) -> Result<OfferResult<(BufferClaim, Position)>> {
+        let mut buffer_claim: MaybeUninit<sys::aeron_buffer_claim_stct> = MaybeUninit::uninit();
+        let ret = unsafe {
+            sys::aeron_exclusive_publication_try_claim(
+                self.inner.as_ptr(),
+                length,
+                buffer_claim.as_mut_ptr(),
+            )
+        };
+        Ok(offer_result(ret)?
+            .map(|position| (BufferClaim::new(unsafe { buffer_claim.assume_init() }), position)))
+    }
+
+    /// Appends a block of complete frames, e.g. one read by
+    /// [`Subscription::block_poll`], to the log. The frames have to carry the
+    /// session and stream id of this publication and start at its current
+    /// term offset.
+    ///
+    /// [`Subscription::block_poll`]: crate::subscription::Subscription::block_poll
+    pub fn offer_block(&mut self, block: &[u8]) -> Result<OfferResult> {
+        offer_result(unsafe {
+            sys::aeron_exclusive_publication_offer_block(
+                self.inner.as_ptr(),
+                block.as_ptr(),
+                block.len(),
+            )
+        })
+    }
+
+    /// Appends a padding frame of `length` bytes that subscribers skip.
+    pub fn append_padding(&mut self, length: usize) -> Result<OfferResult> {
+        offer_result(unsafe {
+            sys::aeron_exclusive_publication_append_padding(self.inner.as_ptr(), length)
+        })
+    }
+}
+
+impl Drop for ExclusivePublication {
+    fn drop(&mut self) {
+        if !self.closed {
+            aeron_result(unsafe {
+                sys::aeron_exclusive_publication_close(self.inner.as_ptr(), None, ptr::null_mut())
+            })
+            .ok();
+        }
+    }
+}
+
+#[must_use = "future must be polled"]
+pub struct AddExclusivePublication {
+    client: Arc<Aeron>,
+    state: AddExclusivePublicationState,
+}
+
+enum AddExclusivePublicationState {
+    Unstarted { uri: String, stream_id: StreamId },
+    Polling { inner: SendSyncPtr<sys::aeron_async_add_exclusive_publication_t> },
+}
+
+impl AddExclusivePublication {
+    pub(crate) fn new(client: &Arc<Aeron>, uri: &str, stream_id: StreamId) -> Result<Self> {
+        Ok(AddExclusivePublication {
+            client: client.clone(),
+            state: AddExclusivePublicationState::Unstarted { uri: uri.to_string(), stream_id },
+        })
+    }
+}
+
+impl Future for AddExclusivePublication {
+    type Output = Result<ExclusivePublication>;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let mut self_mut = self.as_mut();
+        match &self_mut.state {
+            AddExclusivePublicationState::Unstarted { uri, stream_id } => {
+                let s = CString::new(uri.as_bytes())?;
+
+                let mut inner = ptr::null_mut();
+                aeron_result(unsafe {
+                    sys::aeron_async_add_exclusive_publication(
+                        &mut inner,
+                        self_mut.client.inner.as_ptr(),
+                        s.as_ptr(),
+                        stream_id.0,
+                    )
+                })?;
+                debug_assert_ne!(inner, ptr::null_mut());
+
+                self_mut.state = AddExclusivePublicationState::Polling { inner: inner.into() };
+                ctx.waker().wake_by_ref();
+                Poll::Pending
+            }
+            AddExclusivePublicationState::Polling { inner } => {
+                self_mut.client.do_work()?;
+                let mut publication = ptr::null_mut();
+                match unsafe {
+                    sys::aeron_async_add_exclusive_publication_poll(
+                        &mut publication,
+                        inner.as_ptr(),
+                    )
+                } {
+                    0 => {
+                        ctx.waker().wake_by_ref();
+                        Poll::Pending
+                    }
+                    1 => {
+                        debug_assert_ne!(publication, ptr::null_mut());
+                        Poll::Ready(Ok(ExclusivePublication::new(&self_mut.client, publication)))
+                    }
+                    _ => Poll::Ready(Err(aeron_error())),
+                }
+            }
+        }
+    }
+}
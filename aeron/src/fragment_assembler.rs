@@ -0,0 +1,308 @@
+//! Reassembly of messages that were split into fragments because they were
+//! larger than the MTU.
+
+use crate::{subscription::HandlerAction, HeaderRef, SessionId, BEGIN_FRAG_FLAG, END_FRAG_FLAG};
+use std::collections::HashMap;
+
+/// Reassembles fragments into whole messages before they reach a handler.
+///
+/// Each session has a buffer of its own, so the fragments of interleaved
+/// publishers don't mix. Pass the same assembler to every
+/// [`Subscription::poll_assembled`] of a subscription.
+///
+/// [`Subscription::poll_assembled`]: crate::subscription::Subscription::poll_assembled
+#[derive(Debug, Default)]
+pub struct FragmentAssembler {
+    sessions: SessionBuffers,
+}
+
+impl FragmentAssembler {
+    pub fn new() -> Self {
+        FragmentAssembler::default()
+    }
+
+    /// Feeds a fragment to the assembler. Calls `handler` with the whole
+    /// message and the header of its last fragment once the message is
+    /// complete.
+    pub fn on_fragment<F>(&mut self, fragment: &[u8], header: HeaderRef<'_>, mut handler: F)
+    where
+        F: for<'a> FnMut(&'a [u8], HeaderRef<'a>),
+    {
+        self.assemble(fragment, header, |message, header| handler(message, header))
+    }
+
+    fn assemble<H, F>(&mut self, fragment: &[u8], header: H, mut handler: F)
+    where
+        H: FragmentHeader,
+        F: FnMut(&[u8], H),
+    {
+        if header.is_unfragmented() {
+            handler(fragment, header);
+            return;
+        }
+        let session_id = header.session_id();
+        if let Some(message) = self.sessions.append(fragment, header) {
+            handler(message, header);
+            self.sessions.reset(session_id);
+        }
+    }
+
+    /// Drops the partial message of a session, e.g. after its image went away.
+    /// Returns whether the session had a buffer.
+    pub fn free_session_buffer(&mut self, session_id: SessionId) -> bool {
+        self.sessions.buffers.remove(&session_id).is_some()
+    }
+}
+
+/// Like [`FragmentAssembler`], for [`Subscription::controlled_poll_assembled`].
+///
+/// If the handler aborts a message, the last fragment is removed again so
+/// that the message is complete once the fragment is redelivered.
+///
+/// [`Subscription::controlled_poll_assembled`]: crate::subscription::Subscription::controlled_poll_assembled
+#[derive(Debug, Default)]
+pub struct ControlledFragmentAssembler {
+    sessions: SessionBuffers,
+}
+
+impl ControlledFragmentAssembler {
+    pub fn new() -> Self {
+        ControlledFragmentAssembler::default()
+    }
+
+    /// Feeds a fragment to the assembler and returns the action for the
+    /// fragment. The handler only sees whole messages.
+    pub fn on_fragment<F>(
+        &mut self,
+        fragment: &[u8],
+        header: HeaderRef<'_>,
+        mut handler: F,
+    ) -> HandlerAction
+    where
+        F: for<'a> FnMut(&'a [u8], HeaderRef<'a>) -> HandlerAction,
+    {
+        self.assemble(fragment, header, |message, header| handler(message, header))
+    }
+
+    fn assemble<H, F>(&mut self, fragment: &[u8], header: H, mut handler: F) -> HandlerAction
+    where
+        H: FragmentHeader,
+        F: FnMut(&[u8], H) -> HandlerAction,
+    {
+        if header.is_unfragmented() {
+            return handler(fragment, header);
+        }
+        let session_id = header.session_id();
+        let Some(message) = self.sessions.append(fragment, header) else {
+            return HandlerAction::Continue;
+        };
+        let action = handler(message, header);
+        if action == HandlerAction::Abort {
+            self.sessions.truncate(session_id, fragment.len());
+        } else {
+            self.sessions.reset(session_id);
+        }
+        action
+    }
+
+    /// Drops the partial message of a session, e.g. after its image went away.
+    /// Returns whether the session had a buffer.
+    pub fn free_session_buffer(&mut self, session_id: SessionId) -> bool {
+        self.sessions.buffers.remove(&session_id).is_some()
+    }
+}
+
+/// What the assemblers read from a header, so they can be tested without the C
+/// client.
+trait FragmentHeader: Copy {
+    fn session_id(&self) -> SessionId;
+
+    fn flags(&self) -> u8;
+
+    fn is_begin(&self) -> bool {
+        self.flags() & BEGIN_FRAG_FLAG != 0
+    }
+
+    fn is_end(&self) -> bool {
+        self.flags() & END_FRAG_FLAG != 0
+    }
+
+    fn is_unfragmented(&self) -> bool {
+        self.is_begin() && self.is_end()
+    }
+}
+
+impl FragmentHeader for HeaderRef<'_> {
+    fn session_id(&self) -> SessionId {
+        HeaderRef::session_id(self)
+    }
+
+    fn flags(&self) -> u8 {
+        HeaderRef::flags(self)
+    }
+}
+
+#[derive(Debug, Default)]
+struct SessionBuffers {
+    buffers: HashMap<SessionId, SessionBuffer>,
+}
+
+#[derive(Debug, Default)]
+struct SessionBuffer {
+    data: Vec<u8>,
+    // Set by a begin fragment; fragments without a preceding begin fragment,
+    // e.g. after joining mid-message, are dropped.
+    in_progress: bool,
+}
+
+impl SessionBuffers {
+    /// Appends a fragmented part of a message and returns the whole message if
+    /// this was its last fragment.
+    fn append(&mut self, fragment: &[u8], header: impl FragmentHeader) -> Option<&[u8]> {
+        let buffer = self.buffers.entry(header.session_id()).or_default();
+        if header.is_begin() {
+            buffer.data.clear();
+            buffer.in_progress = true;
+        } else if !buffer.in_progress {
+            return None;
+        }
+        buffer.data.extend_from_slice(fragment);
+        if header.is_end() {
+            Some(&buffer.data)
+        } else {
+            None
+        }
+    }
+
+    fn reset(&mut self, session_id: SessionId) {
+        if let Some(buffer) = self.buffers.get_mut(&session_id) {
+            buffer.data.clear();
+            buffer.in_progress = false;
+        }
+    }
+
+    /// Removes the last `length` bytes, keeping the message in progress.
+    fn truncate(&mut self, session_id: SessionId, length: usize) {
+        if let Some(buffer) = self.buffers.get_mut(&session_id) {
+            let len = buffer.data.len().saturating_sub(length);
+            buffer.data.truncate(len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEGIN: u8 = BEGIN_FRAG_FLAG;
+    const MIDDLE: u8 = 0;
+    const END: u8 = END_FRAG_FLAG;
+    const UNFRAGMENTED: u8 = BEGIN_FRAG_FLAG | END_FRAG_FLAG;
+
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    struct FakeHeader {
+        session_id: i32,
+        flags: u8,
+    }
+
+    impl FragmentHeader for FakeHeader {
+        fn session_id(&self) -> SessionId {
+            SessionId(self.session_id)
+        }
+
+        fn flags(&self) -> u8 {
+            self.flags
+        }
+    }
+
+    fn fragment(session_id: i32, flags: u8, data: &[u8]) -> (FakeHeader, Vec<u8>) {
+        (FakeHeader { session_id, flags }, data.to_vec())
+    }
+
+    fn assemble(fragments: &[(FakeHeader, Vec<u8>)]) -> Vec<(i32, Vec<u8>)> {
+        let mut assembler = FragmentAssembler::new();
+        let mut messages = Vec::new();
+        for (header, data) in fragments {
+            assembler.assemble(data, *header, |message, header| {
+                messages.push((header.session_id, message.to_vec()))
+            });
+        }
+        messages
+    }
+
+    #[test]
+    fn reassembles_interleaved_sessions() {
+        let messages = assemble(&[
+            fragment(1, BEGIN, b"a1"),
+            fragment(2, BEGIN, b"b1"),
+            fragment(1, MIDDLE, b"a2"),
+            fragment(2, MIDDLE, b"b2"),
+            fragment(2, END, b"b3"),
+            fragment(1, END, b"a3"),
+        ]);
+        assert_eq!(messages, [(2, b"b1b2b3".to_vec()), (1, b"a1a2a3".to_vec())]);
+    }
+
+    #[test]
+    fn passes_unfragmented_messages_through() {
+        let messages = assemble(&[
+            fragment(1, BEGIN, b"a1"),
+            fragment(2, UNFRAGMENTED, b"b"),
+            fragment(1, UNFRAGMENTED, b"c"),
+            fragment(1, BEGIN, b"d1"),
+            fragment(1, END, b"d2"),
+        ]);
+        assert_eq!(messages, [(2, b"b".to_vec()), (1, b"c".to_vec()), (1, b"d1d2".to_vec())],);
+    }
+
+    #[test]
+    fn drops_fragments_without_begin() {
+        let messages = assemble(&[
+            fragment(1, MIDDLE, b"a2"),
+            fragment(1, END, b"a3"),
+            fragment(1, BEGIN, b"b1"),
+            fragment(1, END, b"b2"),
+        ]);
+        assert_eq!(messages, [(1, b"b1b2".to_vec())]);
+    }
+
+    #[test]
+    fn frees_session_buffers() {
+        let mut assembler = FragmentAssembler::new();
+        let (header, data) = fragment(1, BEGIN, b"a1");
+        assembler.assemble(&data, header, |_, _| panic!("incomplete message"));
+
+        assert!(assembler.free_session_buffer(SessionId(1)));
+        assert!(!assembler.free_session_buffer(SessionId(1)));
+        let (header, data) = fragment(1, END, b"a2");
+        assembler.assemble(&data, header, |_, _| panic!("message without begin"));
+    }
+
+    #[test]
+    fn controlled_keeps_aborted_messages_for_redelivery() {
+        let mut assembler = ControlledFragmentAssembler::new();
+        let mut attempts = Vec::new();
+        let mut on_fragment = |(header, data): (FakeHeader, Vec<u8>)| {
+            assembler.assemble(&data, header, |message, header| {
+                attempts.push((header.session_id, message.to_vec()));
+                if attempts.len() == 1 {
+                    HandlerAction::Abort
+                } else {
+                    HandlerAction::Continue
+                }
+            })
+        };
+
+        assert_eq!(on_fragment(fragment(1, BEGIN, b"a1")), HandlerAction::Continue);
+        assert_eq!(on_fragment(fragment(1, MIDDLE, b"a2")), HandlerAction::Continue);
+        assert_eq!(on_fragment(fragment(1, END, b"a3")), HandlerAction::Abort);
+        // The aborted fragment is polled again.
+        assert_eq!(on_fragment(fragment(1, END, b"a3")), HandlerAction::Continue);
+        assert_eq!(on_fragment(fragment(1, UNFRAGMENTED, b"b")), HandlerAction::Continue);
+
+        assert_eq!(
+            attempts,
+            [(1, b"a1a2a3".to_vec()), (1, b"a1a2a3".to_vec()), (1, b"b".to_vec())],
+        );
+    }
+}
@@ -0,0 +1,256 @@
+//! Idle strategies for duty cycle loops, i.e. loops that poll subscriptions or
+//! do other work and need to wait when there was nothing to do.
+//!
+//! These mirror the `IdleStrategy` implementations of the Java and C clients.
+
+use std::{hint, thread, time::Duration};
+
+/// Decides how a duty cycle loop waits between iterations that did no work.
+pub trait IdleStrategy {
+    /// Called after each iteration with the amount of work it did, e.g. the
+    /// fragments returned by [`Subscription::poll`]. Idles if `work_count` is
+    /// zero, otherwise resets the strategy.
+    ///
+    /// [`Subscription::poll`]: crate::subscription::Subscription::poll
+    fn idle(&mut self, work_count: usize);
+
+    /// Resets the internal state, e.g. the current backoff.
+    fn reset(&mut self) {}
+}
+
+/// Spins without giving up the CPU. For the lowest latency on dedicated cores.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct BusySpin;
+
+impl IdleStrategy for BusySpin {
+    fn idle(&mut self, work_count: usize) {
+        if work_count == 0 {
+            hint::spin_loop();
+        }
+    }
+}
+
+/// Yields the thread to the scheduler.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Yielding;
+
+impl IdleStrategy for Yielding {
+    fn idle(&mut self, work_count: usize) {
+        if work_count == 0 {
+            thread::yield_now();
+        }
+    }
+}
+
+/// Sleeps for a fixed period.
+#[derive(Copy, Clone, Debug)]
+pub struct Sleeping {
+    period: Duration,
+}
+
+impl Sleeping {
+    pub fn new(period: Duration) -> Self {
+        Sleeping { period }
+    }
+}
+
+impl Default for Sleeping {
+    fn default() -> Self {
+        Sleeping::new(Duration::from_micros(1))
+    }
+}
+
+impl IdleStrategy for Sleeping {
+    fn idle(&mut self, work_count: usize) {
+        if work_count == 0 {
+            thread::sleep(self.period);
+        }
+    }
+}
+
+/// Doesn't idle at all, e.g. when the loop is driven by something else.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NoOp;
+
+impl IdleStrategy for NoOp {
+    fn idle(&mut self, _work_count: usize) {}
+}
+
+/// Spins, then yields, then sleeps with an exponentially growing period.
+///
+/// The default spins 10 times, yields 5 times and sleeps from 1µs up to 1ms,
+/// like `BackoffIdleStrategy` of the Java client.
+#[derive(Copy, Clone, Debug)]
+pub struct Backoff {
+    max_spins: u64,
+    max_yields: u64,
+    min_park_period: Duration,
+    max_park_period: Duration,
+    state: BackoffState,
+    spins: u64,
+    yields: u64,
+    park_period: Duration,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum BackoffState {
+    NotIdle,
+    Spinning,
+    Yielding,
+    Parking,
+}
+
+impl Backoff {
+    pub fn new(
+        max_spins: u64,
+        max_yields: u64,
+        min_park_period: Duration,
+        max_park_period: Duration,
+    ) -> Self {
+        Backoff {
+            max_spins,
+            max_yields,
+            min_park_period,
+            max_park_period: max_park_period.max(min_park_period),
+            state: BackoffState::NotIdle,
+            spins: 0,
+            yields: 0,
+            park_period: min_park_period,
+        }
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff::new(10, 5, Duration::from_micros(1), Duration::from_millis(1))
+    }
+}
+
+impl IdleStrategy for Backoff {
+    fn idle(&mut self, work_count: usize) {
+        if work_count > 0 {
+            self.reset();
+            return;
+        }
+        match self.state {
+            BackoffState::NotIdle => {
+                self.state = BackoffState::Spinning;
+                self.spins += 1;
+            }
+            BackoffState::Spinning => {
+                hint::spin_loop();
+                self.spins += 1;
+                if self.spins > self.max_spins {
+                    self.state = BackoffState::Yielding;
+                    self.yields = 0;
+                }
+            }
+            BackoffState::Yielding => {
+                self.yields += 1;
+                if self.yields > self.max_yields {
+                    self.state = BackoffState::Parking;
+                    self.park_period = self.min_park_period;
+                } else {
+                    thread::yield_now();
+                }
+            }
+            BackoffState::Parking => {
+                thread::sleep(self.park_period);
+                self.park_period = (self.park_period * 2).min(self.max_park_period);
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.state = BackoffState::NotIdle;
+        self.spins = 0;
+        self.yields = 0;
+        self.park_period = self.min_park_period;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NANOS: Duration = Duration::from_nanos(1);
+
+    fn assert_state(backoff: &Backoff, state: BackoffState, spins: u64, yields: u64) {
+        assert_eq!((backoff.state, backoff.spins, backoff.yields), (state, spins, yields));
+    }
+
+    #[test]
+    fn backoff_spins_then_yields_then_parks() {
+        let mut backoff = Backoff::new(2, 2, NANOS, 8 * NANOS);
+        assert_state(&backoff, BackoffState::NotIdle, 0, 0);
+
+        backoff.idle(0);
+        assert_state(&backoff, BackoffState::Spinning, 1, 0);
+        backoff.idle(0);
+        assert_state(&backoff, BackoffState::Spinning, 2, 0);
+        backoff.idle(0);
+        assert_state(&backoff, BackoffState::Yielding, 3, 0);
+
+        backoff.idle(0);
+        assert_state(&backoff, BackoffState::Yielding, 3, 1);
+        backoff.idle(0);
+        assert_state(&backoff, BackoffState::Yielding, 3, 2);
+        backoff.idle(0);
+        assert_state(&backoff, BackoffState::Parking, 3, 3);
+        assert_eq!(backoff.park_period, NANOS);
+    }
+
+    #[test]
+    fn backoff_doubles_park_period_up_to_max() {
+        let mut backoff = Backoff::new(0, 0, NANOS, 5 * NANOS);
+        (0..3).for_each(|_| backoff.idle(0));
+        assert_eq!(backoff.state, BackoffState::Parking);
+
+        let mut periods = Vec::new();
+        for _ in 0..5 {
+            periods.push(backoff.park_period);
+            backoff.idle(0);
+        }
+        assert_eq!(periods, [NANOS, 2 * NANOS, 4 * NANOS, 5 * NANOS, 5 * NANOS]);
+    }
+
+    #[test]
+    fn backoff_resets() {
+        let mut backoff = Backoff::new(0, 0, NANOS, 8 * NANOS);
+        (0..5).for_each(|_| backoff.idle(0));
+        assert_eq!((backoff.state, backoff.park_period), (BackoffState::Parking, 4 * NANOS));
+
+        backoff.reset();
+        assert_state(&backoff, BackoffState::NotIdle, 0, 0);
+        assert_eq!(backoff.park_period, NANOS);
+
+        (0..5).for_each(|_| backoff.idle(0));
+        backoff.idle(1);
+        assert_state(&backoff, BackoffState::NotIdle, 0, 0);
+        assert_eq!(backoff.park_period, NANOS);
+    }
+
+    #[test]
+    fn backoff_defaults_to_java_client() {
+        let backoff = Backoff::default();
+        assert_eq!((backoff.max_spins, backoff.max_yields), (10, 5));
+        assert_eq!(backoff.min_park_period, Duration::from_micros(1));
+        assert_eq!(backoff.max_park_period, Duration::from_millis(1));
+    }
+
+    #[test]
+    fn backoff_clamps_max_park_period() {
+        let backoff = Backoff::new(0, 0, 8 * NANOS, NANOS);
+        assert_eq!(backoff.max_park_period, 8 * NANOS);
+    }
+
+    #[test]
+    fn strategies_do_not_idle_after_work() {
+        // Would hang the test if it slept.
+        Sleeping::new(Duration::from_secs(3600)).idle(1);
+        BusySpin.idle(1);
+        Yielding.idle(1);
+        NoOp.idle(0);
+        Sleeping::new(NANOS).idle(0);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{
+    error::{aeron_count, aeron_result, Result},
+    subscription::{
+        block_handler_trampoline, controlled_fragment_handler_trampoline,
+        fragment_handler_trampoline, HandlerAction, Subscription,
+    },
+    CorrelationId, HeaderRef, Position, SendSyncPtr, SessionId, TermId,
+};
+use aeron_client_sys as sys;
+use std::{
+    ffi::{self, CStr},
+    marker::PhantomData,
+    mem::MaybeUninit,
+};
+
+/// The stream of one publisher, i.e. one session, of a subscription.
+///
+/// Images returned by a [`Subscription`] are retained until dropped, so they
+/// stay valid even if the publisher goes away in the meantime.
+pub struct Image<'a> {
+    subscription: SendSyncPtr<sys::aeron_subscription_t>,
+    inner: SendSyncPtr<sys::aeron_image_t>,
+    retained: bool,
+    _subscription: PhantomData<&'a Subscription>,
+}
+
+impl Image<'_> {
+    /// Wraps an image that was retained for the caller and is released on drop.
+    pub(crate) fn retained(
+        subscription: *mut sys::aeron_subscription_t,
+        inner: *mut sys::aeron_image_t,
+    ) -> Self {
+        Image {
+            subscription: subscription.into(),
+            inner: inner.into(),
+            retained: true,
+            _subscription: PhantomData,
+        }
+    }
+
+    /// Wraps an image the C client lends for the duration of a callback.
+    pub(crate) fn borrowed(
+        subscription: *mut sys::aeron_subscription_t,
+        inner: *mut sys::aeron_image_t,
+    ) -> Self {
+        Image {
+            subscription: subscription.into(),
+            inner: inner.into(),
+            retained: false,
+            _subscription: PhantomData,
+        }
+    }
+
+    fn constants(&self) -> sys::aeron_image_constants_t {
+        let mut constants: MaybeUninit<sys::aeron_image_constants_t> = MaybeUninit::zeroed();
+        // Only fails for null arguments.
+        unsafe { sys::aeron_image_constants(self.inner.as_ptr(), constants.as_mut_ptr()) };
+        unsafe { constants.assume_init() }
+    }
+
+    pub fn session_id(&self) -> SessionId {
+        SessionId(self.constants().session_id)
+    }
+
+    pub fn correlation_id(&self) -> CorrelationId {
+        CorrelationId(self.constants().correlation_id)
+    }
+
+    /// The address of the publisher, e.g. `192.168.0.1:40123` for UDP or
+    /// `aeron:ipc` for IPC.
+    pub fn source_identity(&self) -> String {
+        let source_identity = self.constants().source_identity;
+        if source_identity.is_null() {
+            return "".to_owned();
+        }
+        unsafe { CStr::from_ptr(source_identity) }.to_string_lossy().into_owned()
+    }
+
+    /// The position the subscriber joined the stream at.
+    pub fn join_position(&self) -> Position {
+        Position(self.constants().join_position)
+    }
+
+    pub fn initial_term_id(&self) -> TermId {
+        TermId(self.constants().initial_term_id)
+    }
+
+    pub fn term_buffer_length(&self) -> usize {
+        self.constants().term_buffer_length
+    }
+
+    pub fn mtu_length(&self) -> usize {
+        self.constants().mtu_length
+    }
+
+    /// The position up to which this subscriber has consumed the stream.
+    pub fn position(&self) -> Position {
+        Position(unsafe { sys::aeron_image_position(self.inner.as_ptr()) })
+    }
+
+    /// Moves the subscriber to `position`, which has to be a frame boundary
+    /// within the current term.
+    pub fn set_position(&self, position: Position) -> Result<()> {
+        aeron_result(unsafe { sys::aeron_image_set_position(self.inner.as_ptr(), position.0) })
+    }
+
+    /// Whether the publisher closed the stream and all of it has been consumed.
+    pub fn is_end_of_stream(&self) -> bool {
+        unsafe { sys::aeron_image_is_end_of_stream(self.inner.as_ptr()) }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        unsafe { sys::aeron_image_is_closed(self.inner.as_ptr()) }
+    }
+
+    /// Returns the number of fragments read.
+    pub fn poll<F>(&self, handler: F, fragment_limit: usize) -> Result<usize>
+    where
+        F: for<'a> FnMut(&'a [u8], HeaderRef<'a>),
+    {
+        let mut closure = handler;
+        let count = unsafe {
+            sys::aeron_image_poll(
+                self.inner.as_ptr(),
+                Some(fragment_handler_trampoline::<F>),
+                &mut closure as *mut _ as *mut ffi::c_void,
+                fragment_limit,
+            )
+        };
+        aeron_count(i64::from(count))
+    }
+
+    /// Returns the number of fragments read.
+    pub fn controlled_poll<F>(&self, handler: F, fragment_limit: usize) -> Result<usize>
+    where
+        F: for<'a> FnMut(&'a [u8], HeaderRef<'a>) -> HandlerAction,
+    {
+        let mut closure = handler;
+        let count = unsafe {
+            sys::aeron_image_controlled_poll(
+                self.inner.as_ptr(),
+                Some(controlled_fragment_handler_trampoline::<F>),
+                &mut closure as *mut _ as *mut ffi::c_void,
+                fragment_limit,
+            )
+        };
+        aeron_count(i64::from(count))
+    }
+
+    /// Returns the number of bytes read.
+    pub fn block_poll<F>(&self, handler: F, block_length_limit: usize) -> Result<usize>
+    where
+        F: for<'a> FnMut(&'a [u8], SessionId, TermId),
+    {
+        let mut closure = handler;
+        let count = unsafe {
+            sys::aeron_image_block_poll(
+                self.inner.as_ptr(),
+                Some(block_handler_trampoline::<F>),
+                &mut closure as *mut _ as *mut ffi::c_void,
+                block_length_limit,
+            )
+        };
+        aeron_count(i64::from(count))
+    }
+}
+
+impl Drop for Image<'_> {
+    fn drop(&mut self) {
+        if self.retained {
+            aeron_result(unsafe {
+                sys::aeron_subscription_image_release(
+                    self.subscription.as_ptr(),
+                    self.inner.as_ptr(),
+                )
+            })
+            .ok();
+        }
+    }
+}
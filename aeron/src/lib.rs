@@ -1,25 +1,49 @@
+pub mod agent;
+#[cfg(feature = "archive")]
+pub mod archive;
 pub mod client;
+pub mod config;
 pub mod context;
+pub mod counters;
+#[cfg(feature = "embedded-driver")]
+pub mod driver;
 pub mod error;
+pub mod exclusive_publication;
+pub mod fragment_assembler;
+pub mod idle;
+pub mod image;
 pub mod publication;
+#[cfg(feature = "futures")]
+pub mod sink;
+#[cfg(feature = "futures")]
+pub mod stream;
 pub mod subscription;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod timer;
 
 use aeron_client_sys as sys;
-use std::ptr::NonNull;
+use std::{ffi::c_void, fmt, marker::PhantomData, mem::MaybeUninit, ptr::NonNull};
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct StreamId(pub i32);
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct SessionId(pub i32);
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct CorrelationId(pub i64);
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RegistrationId(pub i64);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CounterId(pub i32);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct TermId(pub i32);
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Position(pub i64);
 
 #[derive(Copy, Clone, Debug)]
@@ -30,10 +54,19 @@ pub enum ChannelStatus {
     Other(i64),
 }
 
+const BEGIN_FRAG_FLAG: u8 = 0x80;
+const END_FRAG_FLAG: u8 = 0x40;
+const FRAME_ALIGNMENT: i32 = 32;
+
 #[derive(Clone, Debug)]
 pub struct Header(sys::aeron_header_values_t);
 
 impl Header {
+    /// The length of the frame including its header.
+    pub fn frame_length(&self) -> i32 {
+        self.0.frame.frame_length
+    }
+
     pub fn version(&self) -> i8 {
         self.0.frame.version
     }
@@ -65,6 +98,142 @@ impl Header {
     pub fn reserved_value(&self) -> i64 {
         self.0.frame.reserved_value
     }
+
+    pub fn initial_term_id(&self) -> TermId {
+        TermId(self.0.initial_term_id)
+    }
+
+    pub fn position_bits_to_shift(&self) -> usize {
+        self.0.position_bits_to_shift
+    }
+
+    /// The offset in the term at which the next frame begins.
+    pub fn next_term_offset(&self) -> i32 {
+        let end = self.term_offset() + self.frame_length();
+        (end + FRAME_ALIGNMENT - 1) & !(FRAME_ALIGNMENT - 1)
+    }
+
+    /// The stream position just after this fragment, like
+    /// `aeron_header_position`.
+    pub fn position(&self) -> Position {
+        let term_count = self.0.frame.term_id.wrapping_sub(self.0.initial_term_id) as i64;
+        Position((term_count << self.position_bits_to_shift()) + self.next_term_offset() as i64)
+    }
+
+    /// Whether this is the first fragment of a message.
+    pub fn is_begin(&self) -> bool {
+        self.flags() & BEGIN_FRAG_FLAG != 0
+    }
+
+    /// Whether this is the last fragment of a message.
+    pub fn is_end(&self) -> bool {
+        self.flags() & END_FRAG_FLAG != 0
+    }
+}
+
+/// The header of a fragment, borrowed from the C client while a fragment
+/// handler runs.
+///
+/// Fields are read through the C client on access instead of being copied up
+/// front. Use [`HeaderRef::to_owned`] to keep the header beyond the handler.
+#[derive(Copy, Clone)]
+pub struct HeaderRef<'a> {
+    inner: NonNull<sys::aeron_header_t>,
+    _header: PhantomData<&'a sys::aeron_header_t>,
+}
+
+impl HeaderRef<'_> {
+    pub(crate) fn new(inner: *mut sys::aeron_header_t) -> Self {
+        debug_assert!(!inner.is_null());
+        HeaderRef { inner: unsafe { NonNull::new_unchecked(inner) }, _header: PhantomData }
+    }
+
+    // `aeronc.h` declares `aeron_header_t` opaquely, so the frame is only
+    // readable as a copy of the header values.
+    fn values(&self) -> sys::aeron_header_values_t {
+        let mut values: MaybeUninit<sys::aeron_header_values_t> = MaybeUninit::uninit();
+        // Only fails for null arguments.
+        unsafe {
+            sys::aeron_header_values(self.inner.as_ptr(), values.as_mut_ptr());
+            values.assume_init()
+        }
+    }
+
+    /// The length of the frame including its header.
+    pub fn frame_length(&self) -> i32 {
+        self.values().frame.frame_length
+    }
+
+    pub fn version(&self) -> i8 {
+        self.values().frame.version
+    }
+
+    pub fn flags(&self) -> u8 {
+        self.values().frame.flags
+    }
+
+    pub fn r#type(&self) -> HeaderType {
+        HeaderType(self.values().frame.type_)
+    }
+
+    pub fn session_id(&self) -> SessionId {
+        SessionId(self.values().frame.session_id)
+    }
+
+    pub fn stream_id(&self) -> StreamId {
+        StreamId(self.values().frame.stream_id)
+    }
+
+    pub fn term_id(&self) -> TermId {
+        TermId(self.values().frame.term_id)
+    }
+
+    pub fn term_offset(&self) -> i32 {
+        self.values().frame.term_offset
+    }
+
+    pub fn reserved_value(&self) -> i64 {
+        self.values().frame.reserved_value
+    }
+
+    pub fn initial_term_id(&self) -> TermId {
+        TermId(self.values().initial_term_id)
+    }
+
+    pub fn position_bits_to_shift(&self) -> usize {
+        unsafe { sys::aeron_header_position_bits_to_shift(self.inner.as_ptr()) }
+    }
+
+    /// The offset in the term at which the next frame begins.
+    pub fn next_term_offset(&self) -> i32 {
+        unsafe { sys::aeron_header_next_term_offset(self.inner.as_ptr()) }
+    }
+
+    /// The stream position just after this fragment.
+    pub fn position(&self) -> Position {
+        Position(unsafe { sys::aeron_header_position(self.inner.as_ptr()) })
+    }
+
+    /// Whether this is the first fragment of a message.
+    pub fn is_begin(&self) -> bool {
+        self.flags() & BEGIN_FRAG_FLAG != 0
+    }
+
+    /// Whether this is the last fragment of a message.
+    pub fn is_end(&self) -> bool {
+        self.flags() & END_FRAG_FLAG != 0
+    }
+
+    /// Copies the header.
+    pub fn to_owned(&self) -> Header {
+        Header(self.values())
+    }
+}
+
+impl fmt::Debug for HeaderRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_owned().fmt(f)
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -108,3 +277,68 @@ impl<T> From<*mut T> for SendSyncPtr<T> {
         SendSyncPtr(unsafe { NonNull::new_unchecked(inner) })
     }
 }
+
+/// A boxed closure handed to the C client as `clientd`.
+///
+/// The closure stays at a fixed heap address until this value is dropped, so
+/// the owner must keep it alive for as long as the C client may call into it.
+pub(crate) struct ClientData {
+    ptr: NonNull<c_void>,
+    drop: unsafe fn(*mut c_void),
+}
+
+// The closure is only ever called from C through the raw pointer; Rust never
+// hands out references to it.
+unsafe impl Send for ClientData {}
+unsafe impl Sync for ClientData {}
+
+impl ClientData {
+    pub(crate) fn new<F: Send + 'static>(closure: F) -> Self {
+        unsafe fn drop_boxed<F>(ptr: *mut c_void) {
+            drop(Box::from_raw(ptr as *mut F));
+        }
+        let ptr = Box::into_raw(Box::new(closure)) as *mut c_void;
+        ClientData { ptr: unsafe { NonNull::new_unchecked(ptr) }, drop: drop_boxed::<F> }
+    }
+
+    #[inline(always)]
+    pub(crate) fn as_ptr(&self) -> *mut c_void {
+        self.ptr.as_ptr()
+    }
+}
+
+impl Drop for ClientData {
+    fn drop(&mut self) {
+        unsafe { (self.drop)(self.ptr.as_ptr()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_positions_like_the_c_client() {
+        let header = Header(sys::aeron_header_values_t {
+            frame: sys::aeron_header_values_frame_t {
+                frame_length: 40,
+                version: 0,
+                flags: BEGIN_FRAG_FLAG,
+                type_: 1,
+                term_offset: 64,
+                session_id: 7,
+                stream_id: 1001,
+                term_id: 5,
+                reserved_value: 42,
+            },
+            initial_term_id: 3,
+            position_bits_to_shift: 16,
+        });
+
+        assert!(header.is_begin() && !header.is_end());
+        // The frame ends at 104, so the next one begins at the 32 byte
+        // aligned offset 128, two terms past the initial one.
+        assert_eq!(header.next_term_offset(), 128);
+        assert_eq!(header.position(), Position((2 << 16) + 128));
+    }
+}
@@ -1,14 +1,18 @@
 use crate::{
-    client::Aeron,
-    error::{aeron_error, aeron_result, Result},
+    client::{Aeron, Closing},
+    error::{aeron_error, aeron_result, Error, ErrorCode, Result},
+    timer::Delay,
     ChannelStatus, Position, SendSyncPtr, StreamId,
 };
 use aeron_client_sys as sys;
 use std::{
     ffi,
     ffi::CString,
+    io::IoSlice,
     mem::MaybeUninit,
     slice,
+    task::ready,
+    time::{Duration, Instant},
     {future::Future, pin::Pin, sync::Arc, task::Poll},
     {ptr, task},
 };
@@ -16,11 +20,17 @@ use std::{
 pub struct Publication {
     client: Arc<Aeron>,
     inner: SendSyncPtr<sys::aeron_publication_t>,
+    closed: bool,
 }
 
 impl Publication {
     fn new(client: &Arc<Aeron>, inner: *mut sys::aeron_publication_t) -> Self {
-        Publication { client: client.clone(), inner: inner.into() }
+        Publication { client: client.clone(), inner: inner.into(), closed: false }
+    }
+
+    /// The client this publication was added to.
+    pub fn client(&self) -> &Arc<Aeron> {
+        &self.client
     }
 
     pub fn channel_status(&self) -> ChannelStatus {
@@ -39,6 +49,18 @@ impl Publication {
         unsafe { sys::aeron_publication_is_closed(self.inner.as_ptr()) }
     }
 
+    /// Closes the publication. The returned future resolves once the client
+    /// conductor has released it. Dropping the publication closes it as well, but
+    /// ignores errors.
+    pub fn close(mut self) -> Result<Closing> {
+        let inner = self.inner.as_ptr();
+        let closing = Closing::new(&self.client, |on_close_complete, clientd| unsafe {
+            sys::aeron_publication_close(inner, on_close_complete, clientd)
+        })?;
+        self.closed = true;
+        Ok(closing)
+    }
+
     pub fn offer(&mut self, data: &[u8]) -> Result<OfferResult> {
         let res = unsafe {
             sys::aeron_publication_offer(
@@ -49,15 +71,7 @@ impl Publication {
                 ptr::null_mut(),
             )
         };
-        if res >= 0 {
-            return Ok(OfferResult::Ok(Position(res)));
-        }
-        match res {
-            -1 => Ok(OfferResult::NotConnected),
-            -2 => Ok(OfferResult::BackPressured),
-            -3 => Ok(OfferResult::AdminAction),
-            _ => Err(aeron_error(res as i32)),
-        }
+        offer_result(res)
     }
 
     pub fn offer_with_reserved_value_supplier<F>(
@@ -78,27 +92,59 @@ impl Publication {
                 &mut closure as *mut _ as *mut ffi::c_void,
             )
         };
-        if res >= 0 {
-            return Ok(OfferResult::Ok(Position(res)));
-        }
-        match res {
-            -1 => Ok(OfferResult::NotConnected),
-            -2 => Ok(OfferResult::BackPressured),
-            -3 => Ok(OfferResult::AdminAction),
-            _ => Err(aeron_error(res as i32)),
-        }
+        offer_result(res)
+    }
+
+    /// Offers the concatenation of `bufs` as a single message, without copying
+    /// them into one buffer first.
+    pub fn offer_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<OfferResult> {
+        offer_result(with_iovecs(bufs, |iov, iovcnt| unsafe {
+            sys::aeron_publication_offerv(self.inner.as_ptr(), iov, iovcnt, None, ptr::null_mut())
+        }))
+    }
+
+    pub fn offer_vectored_with_reserved_value_supplier<F>(
+        &mut self,
+        bufs: &[IoSlice<'_>],
+        reserved_value_supplier: F,
+    ) -> Result<OfferResult>
+    where
+        F: for<'a> FnMut(&'a mut [u8]) -> i64,
+    {
+        let mut closure = reserved_value_supplier;
+        offer_result(with_iovecs(bufs, |iov, iovcnt| unsafe {
+            sys::aeron_publication_offerv(
+                self.inner.as_ptr(),
+                iov,
+                iovcnt,
+                Some(reserved_value_supplier_trampoline::<F>),
+                &mut closure as *mut _ as *mut ffi::c_void,
+            )
+        }))
+    }
+
+    /// Offers `data` and retries while the publication is back pressured, see
+    /// [`SendOptions`] for the retry behaviour.
+    pub fn send<'a>(&'a mut self, data: &'a [u8]) -> SendMessage<'a> {
+        self.send_with_options(data, SendOptions::default())
     }
 
-    pub fn try_claim(&mut self, length: usize) -> Result<(BufferClaim, Position)> {
+    pub fn send_with_options<'a>(
+        &'a mut self,
+        data: &'a [u8],
+        options: SendOptions,
+    ) -> SendMessage<'a> {
+        SendMessage::new(self, data, options)
+    }
+
+    pub fn try_claim(&mut self, length: usize) -> Result<OfferResult<(BufferClaim, Position)>> {
         let mut buffer_claim: MaybeUninit<sys::aeron_buffer_claim_stct> = MaybeUninit::uninit();
         let ret = unsafe {
             sys::aeron_publication_try_claim(self.inner.as_ptr(), length, buffer_claim.as_mut_ptr())
         };
-        if ret >= 0 {
-            Ok((BufferClaim { inner: unsafe { buffer_claim.assume_init() } }, Position(ret)))
-        } else {
-            Err(aeron_error(ret as i32))
-        }
+        // The claim is only initialised if the result is a position.
+        Ok(offer_result(ret)?
+            .map(|position| (BufferClaim::new(unsafe { buffer_claim.assume_init() }), position)))
     }
 
     pub fn add_destination(self: &Arc<Self>, uri: &str) -> Result<AsyncDestination> {
@@ -112,7 +158,7 @@ impl Publication {
                 uri.as_ptr(),
             )
         })?;
-        Ok(AsyncDestination { _publication: self.clone(), inner: inner.into() })
+        Ok(AsyncDestination { publication: self.clone(), inner: inner.into() })
     }
 
     pub fn remove_destination(self: &Arc<Self>, uri: &str) -> Result<AsyncDestination> {
@@ -126,24 +172,247 @@ impl Publication {
                 uri.as_ptr(),
             )
         })?;
-        Ok(AsyncDestination { _publication: self.clone(), inner: inner.into() })
+        Ok(AsyncDestination { publication: self.clone(), inner: inner.into() })
     }
 }
 
 impl Drop for Publication {
     fn drop(&mut self) {
-        aeron_result(unsafe {
-            sys::aeron_publication_close(self.inner.as_ptr(), None, ptr::null_mut())
-        })
-        .ok();
+        if !self.closed {
+            aeron_result(unsafe {
+                sys::aeron_publication_close(self.inner.as_ptr(), None, ptr::null_mut())
+            })
+            .ok();
+        }
     }
 }
 
-pub enum OfferResult {
-    Ok(Position),
+/// The outcome of an offer or claim. `T` is the new stream position, plus the
+/// claimed buffer for [`Publication::try_claim`].
+pub enum OfferResult<T = Position> {
+    Ok(T),
+    /// The publication is back pressured by its subscribers.
     BackPressured,
+    /// No subscriber is connected.
     NotConnected,
+    /// The log rotated to the next term, or a similar administrative action
+    /// interrupted the offer.
     AdminAction,
+    /// The publication is closed and won't accept offers again.
+    Closed,
+    /// The stream has reached its maximum position. Offers won't succeed again
+    /// until the stream is recreated, e.g. with a new session id.
+    MaxPositionExceeded,
+}
+
+impl<T> OfferResult<T> {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, OfferResult::Ok(_))
+    }
+
+    /// Whether the same offer may succeed if it is retried later.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            OfferResult::BackPressured | OfferResult::NotConnected | OfferResult::AdminAction
+        )
+    }
+
+    /// Returns the value of a successful offer, `None` if the offer may be
+    /// retried and an error if the publication won't accept offers again.
+    pub fn into_result(self) -> Result<Option<T>> {
+        let code = match self {
+            OfferResult::Ok(value) => return Ok(Some(value)),
+            OfferResult::BackPressured | OfferResult::NotConnected | OfferResult::AdminAction => {
+                return Ok(None)
+            }
+            OfferResult::Closed => ErrorCode::PublicationClosed,
+            OfferResult::MaxPositionExceeded => ErrorCode::MaxPositionExceeded,
+        };
+        Err(Error::Aeron { code, message: code.to_string() })
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> OfferResult<U> {
+        match self {
+            OfferResult::Ok(value) => OfferResult::Ok(f(value)),
+            OfferResult::BackPressured => OfferResult::BackPressured,
+            OfferResult::NotConnected => OfferResult::NotConnected,
+            OfferResult::AdminAction => OfferResult::AdminAction,
+            OfferResult::Closed => OfferResult::Closed,
+            OfferResult::MaxPositionExceeded => OfferResult::MaxPositionExceeded,
+        }
+    }
+}
+
+/// What [`Publication::send`] does if no subscriber is connected.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum NotConnectedPolicy {
+    /// Back off and retry like for back pressure.
+    #[default]
+    Retry,
+    /// Fail with [`Error::NotConnected`].
+    Fail,
+}
+
+/// Controls how [`Publication::send`] retries offers.
+///
+/// Retries back off exponentially from `min_backoff` to `max_backoff`. Without
+/// a timeout, a send retries until the offer succeeds or fails for good.
+#[derive(Debug, Clone)]
+pub struct SendOptions {
+    min_backoff: Duration,
+    max_backoff: Duration,
+    timeout: Option<Duration>,
+    not_connected: NotConnectedPolicy,
+}
+
+impl Default for SendOptions {
+    fn default() -> Self {
+        SendOptions {
+            min_backoff: Duration::from_micros(1),
+            max_backoff: Duration::from_millis(1),
+            timeout: None,
+            not_connected: NotConnectedPolicy::Retry,
+        }
+    }
+}
+
+impl SendOptions {
+    pub fn backoff(mut self, min: Duration, max: Duration) -> Self {
+        self.min_backoff = min;
+        self.max_backoff = max.max(min);
+        self
+    }
+
+    /// Fails the send with [`Error::TimedOut`] if it didn't succeed in time.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn not_connected(mut self, policy: NotConnectedPolicy) -> Self {
+        self.not_connected = policy;
+        self
+    }
+}
+
+/// Resolves to the new stream position once the message was offered.
+///
+/// Fails with an error if the publication is closed or reached its maximum
+/// position, as retrying can't succeed then.
+#[must_use = "future must be polled"]
+pub struct SendMessage<'a> {
+    publication: &'a mut Publication,
+    data: &'a [u8],
+    retry: Retry,
+}
+
+impl<'a> SendMessage<'a> {
+    fn new(publication: &'a mut Publication, data: &'a [u8], options: SendOptions) -> Self {
+        SendMessage { publication, data, retry: Retry::new(options) }
+    }
+}
+
+impl Future for SendMessage<'_> {
+    type Output = Result<Position>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        loop {
+            ready!(this.retry.poll_delay(ctx));
+            this.publication.client.do_work()?;
+            if let Some(position) = this.retry.check(this.publication.offer(this.data)?)? {
+                return Poll::Ready(Ok(position));
+            }
+            this.retry.schedule()?;
+        }
+    }
+}
+
+/// Tracks the backoff and deadline of retried offers according to
+/// [`SendOptions`].
+pub(crate) struct Retry {
+    options: SendOptions,
+    deadline: Option<Instant>,
+    backoff: Duration,
+    delay: Option<Delay>,
+}
+
+impl Retry {
+    pub(crate) fn new(options: SendOptions) -> Self {
+        let mut retry =
+            Retry { deadline: None, backoff: options.min_backoff, options, delay: None };
+        retry.restart();
+        retry
+    }
+
+    /// Starts over with the minimum backoff and a new deadline.
+    pub(crate) fn restart(&mut self) {
+        self.deadline = self.options.timeout.map(|timeout| Instant::now() + timeout);
+        self.backoff = self.options.min_backoff;
+        self.delay = None;
+    }
+
+    /// Returns the value of a successful offer, `None` if it should be retried
+    /// and an error if the offer failed for good.
+    pub(crate) fn check<T>(&self, result: OfferResult<T>) -> Result<Option<T>> {
+        match result {
+            OfferResult::NotConnected if self.options.not_connected == NotConnectedPolicy::Fail => {
+                Err(Error::NotConnected)
+            }
+            result => result.into_result(),
+        }
+    }
+
+    pub(crate) fn not_connected_policy(&self) -> NotConnectedPolicy {
+        self.options.not_connected
+    }
+
+    /// Waits until the scheduled retry is due.
+    pub(crate) fn poll_delay(&mut self, ctx: &mut task::Context<'_>) -> Poll<()> {
+        if let Some(delay) = &mut self.delay {
+            ready!(Pin::new(delay).poll(ctx));
+            self.delay = None;
+        }
+        Poll::Ready(())
+    }
+
+    /// Schedules the next retry, or fails if the deadline has passed.
+    pub(crate) fn schedule(&mut self) -> Result<()> {
+        let now = Instant::now();
+        let mut wake_at = now + self.backoff;
+        if let Some(deadline) = self.deadline {
+            if now >= deadline {
+                return Err(Error::TimedOut);
+            }
+            wake_at = wake_at.min(deadline);
+        }
+        self.backoff = (self.backoff * 2).min(self.options.max_backoff);
+        self.delay = Some(Delay::until(wake_at));
+        Ok(())
+    }
+}
+
+/// Maps the return value of an offer to its outcome.
+pub(crate) fn offer_result(res: i64) -> Result<OfferResult> {
+    if res >= 0 {
+        return Ok(OfferResult::Ok(Position(res)));
+    }
+    match res {
+        -1 => Ok(OfferResult::NotConnected),
+        -2 => Ok(OfferResult::BackPressured),
+        -3 => Ok(OfferResult::AdminAction),
+        -4 => Ok(OfferResult::Closed),
+        -5 => Ok(OfferResult::MaxPositionExceeded),
+        // `AERON_PUBLICATION_ERROR` is the one result that sets the errmsg, e.g.
+        // if the message is longer than the maximum message length.
+        -6 => Err(aeron_error()),
+        // Not set by the C client, so there is no errmsg to report.
+        _ => {
+            let code = ErrorCode::Unknown(i32::try_from(res).unwrap_or(i32::MIN));
+            Err(Error::Aeron { code, message: format!("unexpected offer result {res}") })
+        }
+    }
 }
 
 pub struct BufferClaim {
@@ -151,6 +420,10 @@ pub struct BufferClaim {
 }
 
 impl BufferClaim {
+    pub(crate) fn new(inner: sys::aeron_buffer_claim_stct) -> Self {
+        BufferClaim { inner }
+    }
+
     pub fn data(&mut self) -> &mut [u8] {
         unsafe { slice::from_raw_parts_mut(self.inner.data, self.inner.length) }
     }
@@ -164,7 +437,29 @@ impl BufferClaim {
     }
 }
 
-unsafe extern "C" fn reserved_value_supplier_trampoline<F>(
+/// Passes `bufs` to `f` as the `aeron_iovec_t` array the C client expects. On
+/// Unix `IoSlice` is ABI compatible with `struct iovec`, so no copy is made.
+#[cfg(unix)]
+pub(crate) fn with_iovecs<R>(
+    bufs: &[IoSlice<'_>],
+    f: impl FnOnce(*mut sys::aeron_iovec_t, usize) -> R,
+) -> R {
+    f(bufs.as_ptr() as *mut sys::aeron_iovec_t, bufs.len())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn with_iovecs<R>(
+    bufs: &[IoSlice<'_>],
+    f: impl FnOnce(*mut sys::aeron_iovec_t, usize) -> R,
+) -> R {
+    let mut iov: Vec<sys::aeron_iovec_t> = bufs
+        .iter()
+        .map(|buf| sys::aeron_iovec_t { iov_base: buf.as_ptr() as _, iov_len: buf.len() as _ })
+        .collect();
+    f(iov.as_mut_ptr(), iov.len())
+}
+
+pub(crate) unsafe extern "C" fn reserved_value_supplier_trampoline<F>(
     clientd: *mut ffi::c_void,
     buffer: *mut u8,
     frame_length: usize,
@@ -222,6 +517,7 @@ impl Future for AddPublication {
                 Poll::Pending
             }
             AddPublicationState::Polling { inner } => {
+                self_mut.client.do_work()?;
                 let mut publication = ptr::null_mut();
                 match unsafe {
                     sys::aeron_async_add_publication_poll(&mut publication, inner.as_ptr())
@@ -234,7 +530,7 @@ impl Future for AddPublication {
                         debug_assert_ne!(publication, ptr::null_mut());
                         Poll::Ready(Ok(Publication::new(&self_mut.client, publication)))
                     }
-                    e => Poll::Ready(Err(aeron_error(e))),
+                    _ => Poll::Ready(Err(aeron_error())),
                 }
             }
         }
@@ -242,17 +538,43 @@ impl Future for AddPublication {
 }
 
 pub struct AsyncDestination {
-    _publication: Arc<Publication>,
+    publication: Arc<Publication>,
     inner: SendSyncPtr<sys::aeron_async_destination_t>,
 }
 
 impl AsyncDestination {
     pub fn poll(&self) -> Result<bool> {
+        self.publication.client.do_work()?;
         let res = unsafe { sys::aeron_publication_async_destination_poll(self.inner.as_ptr()) };
         if res >= 0 {
             Ok(res != 0)
         } else {
-            Err(aeron_error(res))
+            Err(aeron_error())
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_offer_results() {
+        assert!(matches!(offer_result(64), Ok(OfferResult::Ok(Position(64)))));
+        assert!(matches!(offer_result(-1), Ok(OfferResult::NotConnected)));
+        assert!(matches!(offer_result(-2), Ok(OfferResult::BackPressured)));
+        assert!(matches!(offer_result(-3), Ok(OfferResult::AdminAction)));
+        assert!(matches!(offer_result(-4), Ok(OfferResult::Closed)));
+        assert!(matches!(offer_result(-5), Ok(OfferResult::MaxPositionExceeded)));
+        assert!(matches!(offer_result(-6), Err(Error::Aeron { .. })));
+        assert!(matches!(offer_result(-7), Err(Error::Aeron { code: ErrorCode::Unknown(-7), .. })));
+    }
+
+    #[test]
+    fn fails_offers_that_cannot_be_retried() {
+        let code = |result: OfferResult| result.into_result().err().and_then(|e| e.code());
+        assert_eq!(code(OfferResult::Closed), Some(ErrorCode::PublicationClosed));
+        assert_eq!(code(OfferResult::MaxPositionExceeded), Some(ErrorCode::MaxPositionExceeded));
+        assert!(matches!(OfferResult::<Position>::BackPressured.into_result(), Ok(None)));
+    }
+}
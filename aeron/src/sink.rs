@@ -0,0 +1,160 @@
+//! [`Sink`] adapter for publications, enabled by the `futures` feature.
+
+use crate::{
+    client::Aeron,
+    error::{Error, Result},
+    exclusive_publication::ExclusivePublication,
+    publication::{NotConnectedPolicy, OfferResult, Publication, Retry, SendOptions},
+};
+use bytes::Bytes;
+use futures_sink::Sink;
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{self, ready, Poll},
+};
+
+/// A publication that a [`PublicationSink`] can offer to.
+pub trait Offer {
+    fn offer(&mut self, data: &[u8]) -> Result<OfferResult>;
+
+    fn is_connected(&self) -> bool;
+
+    /// The client the publication was added to. The sink drives its
+    /// conductor while it waits.
+    fn client(&self) -> &Arc<Aeron>;
+}
+
+impl Offer for Publication {
+    fn offer(&mut self, data: &[u8]) -> Result<OfferResult> {
+        Publication::offer(self, data)
+    }
+
+    fn is_connected(&self) -> bool {
+        Publication::is_connected(self)
+    }
+
+    fn client(&self) -> &Arc<Aeron> {
+        Publication::client(self)
+    }
+}
+
+impl Offer for ExclusivePublication {
+    fn offer(&mut self, data: &[u8]) -> Result<OfferResult> {
+        ExclusivePublication::offer(self, data)
+    }
+
+    fn is_connected(&self) -> bool {
+        ExclusivePublication::is_connected(self)
+    }
+
+    fn client(&self) -> &Arc<Aeron> {
+        ExclusivePublication::client(self)
+    }
+}
+
+/// Offers each item as one message.
+///
+/// `start_send` offers right away. If the publication is back pressured, the
+/// item is kept and retried with the backoff of the [`SendOptions`], and
+/// `poll_ready` stays pending until it was offered. `poll_ready` also waits
+/// for a subscriber to connect, or fails with [`Error::NotConnected`] if the
+/// options use [`NotConnectedPolicy::Fail`].
+pub struct PublicationSink<P> {
+    publication: P,
+    pending: Option<Bytes>,
+    retry: Retry,
+    waiting: bool,
+}
+
+impl<P: Offer> PublicationSink<P> {
+    pub fn new(publication: P) -> Self {
+        PublicationSink::with_options(publication, SendOptions::default())
+    }
+
+    pub fn with_options(publication: P, options: SendOptions) -> Self {
+        PublicationSink { publication, pending: None, retry: Retry::new(options), waiting: false }
+    }
+
+    pub fn get_ref(&self) -> &P {
+        &self.publication
+    }
+
+    pub fn get_mut(&mut self) -> &mut P {
+        &mut self.publication
+    }
+
+    /// Returns the publication. An item that wasn't offered yet is lost.
+    pub fn into_inner(self) -> P {
+        self.publication
+    }
+
+    /// Offers the pending item and returns whether it was sent. The item is
+    /// dropped if the offer failed for good.
+    fn offer_pending(&mut self) -> Result<bool> {
+        let Some(item) = self.pending.take() else {
+            return Ok(true);
+        };
+        if self.retry.check(self.publication.offer(&item)?)?.is_none() {
+            self.pending = Some(item);
+            return Ok(false);
+        }
+        Ok(true)
+    }
+}
+
+impl<P: Offer + Unpin> Sink<Bytes> for PublicationSink<P> {
+    type Error = Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Result<()>> {
+        ready!(self.as_mut().poll_flush(ctx))?;
+        let this = self.get_mut();
+        loop {
+            ready!(this.retry.poll_delay(ctx));
+            this.publication.client().do_work()?;
+            if this.publication.is_connected() {
+                this.waiting = false;
+                return Poll::Ready(Ok(()));
+            }
+            if this.retry.not_connected_policy() == NotConnectedPolicy::Fail {
+                return Poll::Ready(Err(Error::NotConnected));
+            }
+            if !this.waiting {
+                this.waiting = true;
+                this.retry.restart();
+            }
+            if let Err(e) = this.retry.schedule() {
+                this.waiting = false;
+                return Poll::Ready(Err(e));
+            }
+        }
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Bytes) -> Result<()> {
+        let this = self.get_mut();
+        debug_assert!(this.pending.is_none(), "start_send without poll_ready");
+        this.pending = Some(item);
+        this.retry.restart();
+        this.offer_pending()?;
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Result<()>> {
+        let this = self.get_mut();
+        while this.pending.is_some() {
+            ready!(this.retry.poll_delay(ctx));
+            this.publication.client().do_work()?;
+            if !this.offer_pending()? {
+                if let Err(e) = this.retry.schedule() {
+                    this.pending = None;
+                    return Poll::Ready(Err(e));
+                }
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Result<()>> {
+        self.poll_flush(ctx)
+    }
+}
@@ -0,0 +1,129 @@
+//! [`Stream`] adapter for subscriptions, enabled by the `futures` feature.
+
+use crate::{
+    error::{Error, Result},
+    subscription::Subscription,
+    timer::Delay,
+    Header,
+};
+use bytes::Bytes;
+use futures_core::Stream;
+use std::{
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    task::{self, ready, Poll},
+    time::{Duration, Instant},
+};
+
+/// Yields the fragments of a subscription as owned messages.
+///
+/// Created by [`Subscription::into_stream`]. If a poll finds no fragments, the
+/// stream waits before polling again, backing off exponentially from the
+/// minimum to the maximum idle backoff.
+///
+/// The stream ends once the subscription is closed. If driving the conductor
+/// or polling the subscription fails, the stream yields the error after the
+/// messages received before it, and then ends.
+pub struct SubscriptionStream {
+    subscription: Subscription,
+    fragment_limit: usize,
+    min_idle_backoff: Duration,
+    max_idle_backoff: Duration,
+    backoff: Duration,
+    delay: Option<Delay>,
+    received: VecDeque<(Bytes, Header)>,
+    error: Option<Error>,
+    done: bool,
+}
+
+impl SubscriptionStream {
+    pub(crate) fn new(subscription: Subscription) -> Self {
+        let min_idle_backoff = Duration::from_micros(10);
+        SubscriptionStream {
+            subscription,
+            fragment_limit: 10,
+            min_idle_backoff,
+            max_idle_backoff: Duration::from_millis(1),
+            backoff: min_idle_backoff,
+            delay: None,
+            received: VecDeque::new(),
+            error: None,
+            done: false,
+        }
+    }
+
+    /// The maximum number of fragments read by one poll of the subscription.
+    pub fn fragment_limit(mut self, limit: usize) -> Self {
+        self.fragment_limit = limit.max(1);
+        self
+    }
+
+    pub fn idle_backoff(mut self, min: Duration, max: Duration) -> Self {
+        self.min_idle_backoff = min;
+        self.max_idle_backoff = max.max(min);
+        self.backoff = min;
+        self
+    }
+
+    pub fn get_ref(&self) -> &Subscription {
+        &self.subscription
+    }
+
+    /// Returns the subscription. Fragments that were polled but not yielded
+    /// yet are lost.
+    pub fn into_inner(self) -> Subscription {
+        self.subscription
+    }
+}
+
+impl Stream for SubscriptionStream {
+    type Item = Result<(Bytes, Header)>;
+
+    fn poll_next(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(message) = this.received.pop_front() {
+                return Poll::Ready(Some(Ok(message)));
+            }
+            if let Some(e) = this.error.take() {
+                return Poll::Ready(Some(Err(e)));
+            }
+            if this.done {
+                return Poll::Ready(None);
+            }
+            if let Some(delay) = &mut this.delay {
+                ready!(Pin::new(delay).poll(ctx));
+                this.delay = None;
+            }
+            if this.subscription.is_closed() {
+                this.done = true;
+                return Poll::Ready(None);
+            }
+            if let Err(e) = this.subscription.client().do_work() {
+                this.done = true;
+                return Poll::Ready(Some(Err(e)));
+            }
+
+            let received = &mut this.received;
+            let polled = this.subscription.poll(
+                |data, header| {
+                    received.push_back((Bytes::copy_from_slice(data), header.to_owned()))
+                },
+                this.fragment_limit,
+            );
+            match polled {
+                Ok(0) => {
+                    this.delay = Some(Delay::until(Instant::now() + this.backoff));
+                    this.backoff = (this.backoff * 2).min(this.max_idle_backoff);
+                }
+                Ok(_) => this.backoff = this.min_idle_backoff,
+                // Fragments delivered before the error are yielded first.
+                Err(e) => {
+                    this.error = Some(e);
+                    this.done = true;
+                }
+            }
+        }
+    }
+}
@@ -1,14 +1,17 @@
+#[cfg(feature = "futures")]
+use crate::stream::SubscriptionStream;
 use crate::{
-    client::Aeron,
-    error::{aeron_error, aeron_result, Result},
-    ChannelStatus, Header, SendSyncPtr, SessionId, StreamId, TermId,
+    client::{Aeron, Closing},
+    error::{aeron_count, aeron_error, aeron_result, Result},
+    fragment_assembler::{ControlledFragmentAssembler, FragmentAssembler},
+    image::Image,
+    ChannelStatus, ClientData, HeaderRef, SendSyncPtr, SessionId, StreamId, TermId,
 };
 use aeron_client_sys as sys;
 use std::{
     ffi,
     ffi::CString,
-    mem::MaybeUninit,
-    slice,
+    mem, slice,
     {future::Future, pin::Pin, sync::Arc, task::Poll},
     {ptr, task},
 };
@@ -16,11 +19,23 @@ use std::{
 pub struct Subscription {
     client: Arc<Aeron>,
     inner: SendSyncPtr<sys::aeron_subscription_t>,
+    // Image handlers; released once the close of the subscription completed.
+    image_handlers: Vec<ClientData>,
+    closed: bool,
 }
 
 impl Subscription {
-    fn new(client: &Arc<Aeron>, inner: *mut sys::aeron_subscription_t) -> Self {
-        Subscription { client: client.clone(), inner: inner.into() }
+    fn new(
+        client: &Arc<Aeron>,
+        inner: *mut sys::aeron_subscription_t,
+        image_handlers: Vec<ClientData>,
+    ) -> Self {
+        Subscription { client: client.clone(), inner: inner.into(), image_handlers, closed: false }
+    }
+
+    /// The client this subscription was added to.
+    pub fn client(&self) -> &Arc<Aeron> {
+        &self.client
     }
 
     pub fn add_destination(self: &Arc<Self>, uri: &str) -> Result<AsyncDestination> {
@@ -34,7 +49,7 @@ impl Subscription {
                 uri.as_ptr(),
             )
         })?;
-        Ok(AsyncDestination { _subscription: self.clone(), inner: inner.into() })
+        Ok(AsyncDestination { subscription: self.clone(), inner: inner.into() })
     }
 
     pub fn remove_destination(self: &Arc<Self>, uri: &str) -> Result<AsyncDestination> {
@@ -48,7 +63,7 @@ impl Subscription {
                 uri.as_ptr(),
             )
         })?;
-        Ok(AsyncDestination { _subscription: self.clone(), inner: inner.into() })
+        Ok(AsyncDestination { subscription: self.clone(), inner: inner.into() })
     }
 
     pub fn channel_status(&self) -> ChannelStatus {
@@ -67,12 +82,62 @@ impl Subscription {
         unsafe { sys::aeron_subscription_is_closed(self.inner.as_ptr()) }
     }
 
-    pub fn poll<F>(&self, handler: F, fragment_limit: usize)
+    /// Closes the subscription. The returned future resolves once the client
+    /// conductor has released it. Dropping the subscription closes it as well, but
+    /// ignores errors.
+    pub fn close(mut self) -> Result<Closing> {
+        let inner = self.inner.as_ptr();
+        let closing = Closing::new(&self.client, |on_close_complete, clientd| unsafe {
+            sys::aeron_subscription_close(inner, on_close_complete, clientd)
+        })?;
+        // Only released once the close succeeded, the conductor keeps calling
+        // the image handlers otherwise.
+        closing.retain(mem::take(&mut self.image_handlers));
+        self.closed = true;
+        Ok(closing)
+    }
+
+    pub fn image_count(&self) -> Result<usize> {
+        let count = unsafe { sys::aeron_subscription_image_count(self.inner.as_ptr()) };
+        if count < 0 {
+            return Err(aeron_error());
+        }
+        Ok(count as usize)
+    }
+
+    pub fn image_by_session_id(&self, session_id: SessionId) -> Option<Image<'_>> {
+        let image = unsafe {
+            sys::aeron_subscription_image_by_session_id(self.inner.as_ptr(), session_id.0)
+        };
+        (!image.is_null()).then(|| Image::retained(self.inner.as_ptr(), image))
+    }
+
+    pub fn image_at_index(&self, index: usize) -> Option<Image<'_>> {
+        let image = unsafe { sys::aeron_subscription_image_at_index(self.inner.as_ptr(), index) };
+        (!image.is_null()).then(|| Image::retained(self.inner.as_ptr(), image))
+    }
+
+    /// The images currently connected to the subscription, one per publisher.
+    pub fn images(&self) -> Result<Vec<Image<'_>>> {
+        let count = self.image_count()?;
+        Ok((0..count).filter_map(|index| self.image_at_index(index)).collect())
+    }
+
+    /// Turns the subscription into a [`Stream`] of owned messages.
+    ///
+    /// [`Stream`]: futures_core::Stream
+    #[cfg(feature = "futures")]
+    pub fn into_stream(self) -> SubscriptionStream {
+        SubscriptionStream::new(self)
+    }
+
+    /// Returns the number of fragments read.
+    pub fn poll<F>(&self, handler: F, fragment_limit: usize) -> Result<usize>
     where
-        F: for<'a> FnMut(&'a [u8], Header),
+        F: for<'a> FnMut(&'a [u8], HeaderRef<'a>),
     {
         let mut closure = handler;
-        unsafe {
+        let count = unsafe {
             sys::aeron_subscription_poll(
                 self.inner.as_ptr(),
                 Some(fragment_handler_trampoline::<F>),
@@ -80,14 +145,16 @@ impl Subscription {
                 fragment_limit,
             )
         };
+        aeron_count(i64::from(count))
     }
 
-    pub fn controlled_poll<F>(&self, handler: F, fragment_limit: usize)
+    /// Returns the number of fragments read.
+    pub fn controlled_poll<F>(&self, handler: F, fragment_limit: usize) -> Result<usize>
     where
-        F: for<'a> FnMut(&'a [u8], sys::aeron_header_values_t) -> HandlerAction,
+        F: for<'a> FnMut(&'a [u8], HeaderRef<'a>) -> HandlerAction,
     {
         let mut closure = handler;
-        unsafe {
+        let count = unsafe {
             sys::aeron_subscription_controlled_poll(
                 self.inner.as_ptr(),
                 Some(controlled_fragment_handler_trampoline::<F>),
@@ -95,14 +162,50 @@ impl Subscription {
                 fragment_limit,
             )
         };
+        aeron_count(i64::from(count))
+    }
+
+    /// Polls like [`Subscription::poll`], but passes whole messages to the
+    /// handler after reassembling them with `assembler`.
+    pub fn poll_assembled<F>(
+        &self,
+        assembler: &mut FragmentAssembler,
+        mut handler: F,
+        fragment_limit: usize,
+    ) -> Result<usize>
+    where
+        F: for<'a> FnMut(&'a [u8], HeaderRef<'a>),
+    {
+        self.poll(
+            |fragment, header| assembler.on_fragment(fragment, header, &mut handler),
+            fragment_limit,
+        )
+    }
+
+    /// Polls like [`Subscription::controlled_poll`], but passes whole messages
+    /// to the handler after reassembling them with `assembler`.
+    pub fn controlled_poll_assembled<F>(
+        &self,
+        assembler: &mut ControlledFragmentAssembler,
+        mut handler: F,
+        fragment_limit: usize,
+    ) -> Result<usize>
+    where
+        F: for<'a> FnMut(&'a [u8], HeaderRef<'a>) -> HandlerAction,
+    {
+        self.controlled_poll(
+            |fragment, header| assembler.on_fragment(fragment, header, &mut handler),
+            fragment_limit,
+        )
     }
 
-    pub fn block_poll<F>(&self, handler: F, block_length_limit: usize)
+    /// Returns the number of bytes read.
+    pub fn block_poll<F>(&self, handler: F, block_length_limit: usize) -> Result<usize>
     where
         F: for<'a> FnMut(&'a [u8], SessionId, TermId),
     {
         let mut closure = handler;
-        unsafe {
+        let count = unsafe {
             sys::aeron_subscription_block_poll(
                 self.inner.as_ptr(),
                 Some(block_handler_trampoline::<F>),
@@ -110,50 +213,73 @@ impl Subscription {
                 block_length_limit,
             )
         };
+        // Unlike the other polls this returns `c_long`, which is only narrower
+        // than `i64` on Windows and 32 bit targets.
+        #[cfg_attr(
+            all(not(windows), target_pointer_width = "64"),
+            allow(clippy::useless_conversion)
+        )]
+        let count = i64::from(count);
+        aeron_count(count)
     }
 }
 
 impl Drop for Subscription {
     fn drop(&mut self) {
-        aeron_result(unsafe {
-            sys::aeron_subscription_close(self.inner.as_ptr(), None, ptr::null_mut())
-        })
-        .ok();
-        // TODO: err
+        if self.closed {
+            return;
+        }
+        if self.image_handlers.is_empty() {
+            aeron_result(unsafe {
+                sys::aeron_subscription_close(self.inner.as_ptr(), None, ptr::null_mut())
+            })
+            .ok();
+        } else {
+            // The image handlers are released by the close notification. If the
+            // close failed, the conductor may still call them, so they leak.
+            let image_handlers = mem::take(&mut self.image_handlers);
+            let inner = self.inner.as_ptr();
+            match Closing::new(&self.client, |on_close_complete, clientd| unsafe {
+                sys::aeron_subscription_close(inner, on_close_complete, clientd)
+            }) {
+                Ok(closing) => closing.retain(image_handlers),
+                Err(_) => mem::forget(image_handlers),
+            }
+        }
     }
 }
 
 pub struct AsyncDestination {
-    _subscription: Arc<Subscription>,
+    subscription: Arc<Subscription>,
     inner: SendSyncPtr<sys::aeron_async_destination_t>,
 }
 
 impl AsyncDestination {
     pub fn poll(&self) -> Result<bool> {
+        self.subscription.client.do_work()?;
         let res = unsafe { sys::aeron_subscription_async_destination_poll(self.inner.as_ptr()) };
         if res >= 0 {
             Ok(res != 0)
         } else {
-            Err(aeron_error(res))
+            Err(aeron_error())
         }
     }
 }
 
-unsafe extern "C" fn fragment_handler_trampoline<F>(
+pub(crate) unsafe extern "C" fn fragment_handler_trampoline<F>(
     clientd: *mut ffi::c_void,
     fragment: *const u8,
     fragment_length: usize,
     header: *mut sys::aeron_header_t,
 ) where
-    F: for<'a> FnMut(&'a [u8], Header),
+    F: for<'a> FnMut(&'a [u8], HeaderRef<'a>),
 {
-    let mut values: MaybeUninit<sys::aeron_header_values_t> = MaybeUninit::uninit();
-    sys::aeron_header_values(header, values.as_mut_ptr()); // TODO: err
     let closure = &mut *(clientd as *mut F);
     let fragment = slice::from_raw_parts(fragment, fragment_length);
-    closure(fragment, Header(values.assume_init()));
+    closure(fragment, HeaderRef::new(header));
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[repr(u32)]
 pub enum HandlerAction {
     Continue = sys::aeron_controlled_fragment_handler_action_en_AERON_ACTION_CONTINUE,
@@ -162,24 +288,21 @@ pub enum HandlerAction {
     Commit = sys::aeron_controlled_fragment_handler_action_en_AERON_ACTION_COMMIT,
 }
 
-// TODO: replace aeron_header_values_t with custom type
-unsafe extern "C" fn controlled_fragment_handler_trampoline<F>(
+pub(crate) unsafe extern "C" fn controlled_fragment_handler_trampoline<F>(
     clientd: *mut ffi::c_void,
     buffer: *const u8,
     length: usize,
     header: *mut sys::aeron_header_t,
 ) -> u32
 where
-    F: for<'a> FnMut(&'a [u8], sys::aeron_header_values_t) -> HandlerAction,
+    F: for<'a> FnMut(&'a [u8], HeaderRef<'a>) -> HandlerAction,
 {
-    let mut values: MaybeUninit<sys::aeron_header_values_t> = MaybeUninit::uninit();
-    sys::aeron_header_values(header, values.as_mut_ptr()); // TODO: err
     let closure = &mut *(clientd as *mut F);
     let fragment = slice::from_raw_parts(buffer, length);
-    closure(fragment, values.assume_init()) as u32
+    closure(fragment, HeaderRef::new(header)) as u32
 }
 
-unsafe extern "C" fn block_handler_trampoline<F>(
+pub(crate) unsafe extern "C" fn block_handler_trampoline<F>(
     clientd: *mut ffi::c_void,
     buffer: *const u8,
     length: usize,
@@ -197,6 +320,42 @@ unsafe extern "C" fn block_handler_trampoline<F>(
 pub struct AddSubscription {
     client: Arc<Aeron>,
     state: AddSubscriptionState,
+    on_available_image: Option<ImageHandler>,
+    on_unavailable_image: Option<ImageHandler>,
+}
+
+struct ImageHandler {
+    closure: ClientData,
+    trampoline: sys::aeron_on_available_image_t,
+}
+
+impl ImageHandler {
+    fn new<F>(handler: F) -> Self
+    where
+        F: for<'a> FnMut(&Image<'a>) + Send + 'static,
+    {
+        ImageHandler { closure: ClientData::new(handler), trampoline: Some(image_trampoline::<F>) }
+    }
+
+    fn as_raw(
+        handler: &Option<ImageHandler>,
+    ) -> (sys::aeron_on_available_image_t, *mut ffi::c_void) {
+        match handler {
+            Some(handler) => (handler.trampoline, handler.closure.as_ptr()),
+            None => (None, ptr::null_mut()),
+        }
+    }
+}
+
+unsafe extern "C" fn image_trampoline<F>(
+    clientd: *mut ffi::c_void,
+    subscription: *mut sys::aeron_subscription_t,
+    image: *mut sys::aeron_image_t,
+) where
+    F: for<'a> FnMut(&Image<'a>),
+{
+    let closure = &mut *(clientd as *mut F);
+    closure(&Image::borrowed(subscription, image));
 }
 
 enum AddSubscriptionState {
@@ -209,8 +368,48 @@ impl AddSubscription {
         Ok(AddSubscription {
             client: client.clone(),
             state: AddSubscriptionState::Unstarted { uri: uri.to_string(), stream_id },
+            on_available_image: None,
+            on_unavailable_image: None,
         })
     }
+
+    /// Called on the conductor thread when a publisher's image becomes
+    /// available, before the subscription can poll it.
+    pub fn on_available_image<F>(mut self, handler: F) -> Self
+    where
+        F: for<'a> FnMut(&Image<'a>) + Send + 'static,
+    {
+        self.on_available_image = Some(ImageHandler::new(handler));
+        self
+    }
+
+    /// Called on the conductor thread when a publisher's image goes away,
+    /// e.g. because the publisher closed or timed out.
+    pub fn on_unavailable_image<F>(mut self, handler: F) -> Self
+    where
+        F: for<'a> FnMut(&Image<'a>) + Send + 'static,
+    {
+        self.on_unavailable_image = Some(ImageHandler::new(handler));
+        self
+    }
+
+    fn take_image_handlers(&mut self) -> Vec<ClientData> {
+        [self.on_available_image.take(), self.on_unavailable_image.take()]
+            .into_iter()
+            .flatten()
+            .map(|handler| handler.closure)
+            .collect()
+    }
+}
+
+impl Drop for AddSubscription {
+    fn drop(&mut self) {
+        // The conductor may still add the subscription and call the image
+        // handlers, so they have to be leaked.
+        if let AddSubscriptionState::Polling { .. } = self.state {
+            mem::forget(self.take_image_handlers());
+        }
+    }
 }
 
 impl Future for AddSubscription {
@@ -221,6 +420,10 @@ impl Future for AddSubscription {
         match &self_mut.state {
             AddSubscriptionState::Unstarted { uri, stream_id } => {
                 let s = CString::new(uri.as_bytes())?;
+                let (on_available, on_available_clientd) =
+                    ImageHandler::as_raw(&self_mut.on_available_image);
+                let (on_unavailable, on_unavailable_clientd) =
+                    ImageHandler::as_raw(&self_mut.on_unavailable_image);
 
                 let mut inner = ptr::null_mut();
                 aeron_result(unsafe {
@@ -229,10 +432,10 @@ impl Future for AddSubscription {
                         self_mut.client.inner.as_ptr(),
                         s.as_ptr(),
                         stream_id.0,
-                        None, // TODO: on_available_image_handler
-                        ptr::null_mut(),
-                        None, // TODO: on_unavailable_image_handler
-                        ptr::null_mut(),
+                        on_available,
+                        on_available_clientd,
+                        on_unavailable,
+                        on_unavailable_clientd,
                     )
                 })?;
                 self_mut.state = AddSubscriptionState::Polling { inner: inner.into() };
@@ -240,6 +443,7 @@ impl Future for AddSubscription {
                 Poll::Pending
             }
             AddSubscriptionState::Polling { inner } => {
+                self_mut.client.do_work()?;
                 let mut subscription = ptr::null_mut();
                 match unsafe {
                     sys::aeron_async_add_subscription_poll(&mut subscription, inner.as_ptr())
@@ -250,9 +454,17 @@ impl Future for AddSubscription {
                     }
                     1 => {
                         debug_assert_ne!(subscription, ptr::null_mut());
-                        Poll::Ready(Ok(Subscription::new(&self_mut.client, subscription)))
+                        let image_handlers = self_mut.take_image_handlers();
+                        Poll::Ready(Ok(Subscription::new(
+                            &self_mut.client,
+                            subscription,
+                            image_handlers,
+                        )))
+                    }
+                    _ => {
+                        self_mut.take_image_handlers();
+                        Poll::Ready(Err(aeron_error()))
                     }
-                    e => Poll::Ready(Err(aeron_error(e))),
                 }
             }
         }
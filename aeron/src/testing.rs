@@ -0,0 +1,163 @@
+//! Helpers for integration tests that need a running media driver.
+//!
+//! [`TestDriver`] gives every test its own `aeron.dir` in the system temp
+//! directory, so tests can run in parallel without sharing a driver.
+
+#[cfg(feature = "embedded-driver")]
+use crate::driver::{MediaDriver, ThreadingMode};
+use crate::{client::Aeron, context::Context, error::Result};
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    future::Future,
+    io,
+    path::{Path, PathBuf},
+    pin::pin,
+    process::{Child, Command},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{self, Poll, Wake},
+    thread::{self, Thread},
+    time::{Duration, Instant},
+};
+
+const DRIVER_START_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A media driver with its own `aeron.dir`. Stops the driver and removes the
+/// directory when dropped, so it has to outlive all clients connected to it.
+pub struct TestDriver {
+    dir: PathBuf,
+    driver: Option<Driver>,
+}
+
+enum Driver {
+    #[cfg(feature = "embedded-driver")]
+    Embedded(MediaDriver),
+    External(Child),
+}
+
+impl TestDriver {
+    /// Starts an embedded driver, as the `embedded-driver` feature is enabled.
+    #[cfg(feature = "embedded-driver")]
+    pub fn launch() -> Result<Self> {
+        Self::launch_embedded()
+    }
+
+    /// Starts an external driver. Enable the `embedded-driver` feature to run
+    /// the driver in-process instead.
+    #[cfg(not(feature = "embedded-driver"))]
+    pub fn launch() -> Result<Self> {
+        Self::launch_external()
+    }
+
+    #[cfg(feature = "embedded-driver")]
+    pub fn launch_embedded() -> Result<Self> {
+        let dir = unique_dir();
+        let driver = MediaDriver::builder()
+            .dir(dir.to_string_lossy())
+            .dir_delete_on_start(true)
+            .dir_delete_on_shutdown(true)
+            .threading_mode(ThreadingMode::Shared)
+            .term_buffer_sparse_file(true)
+            .term_buffer_length(64 * 1024)
+            .ipc_term_buffer_length(64 * 1024)
+            .launch()?;
+        Ok(TestDriver { dir, driver: Some(Driver::Embedded(driver)) })
+    }
+
+    /// Starts `aeronmd` as a child process. The binary is taken from the
+    /// `AERONMD` environment variable and looked up in `PATH` otherwise.
+    pub fn launch_external() -> Result<Self> {
+        let program = env::var_os("AERONMD").unwrap_or_else(|| OsString::from("aeronmd"));
+        let dir = unique_dir();
+        let child = Command::new(program)
+            .env("AERON_DIR", &dir)
+            .env("AERON_DIR_DELETE_ON_START", "true")
+            .env("AERON_DIR_DELETE_ON_SHUTDOWN", "true")
+            .env("AERON_THREADING_MODE", "SHARED")
+            .env("AERON_TERM_BUFFER_SPARSE_FILE", "true")
+            .env("AERON_TERM_BUFFER_LENGTH", "65536")
+            .env("AERON_IPC_TERM_BUFFER_LENGTH", "65536")
+            .spawn()?;
+        let mut driver = TestDriver { dir, driver: Some(Driver::External(child)) };
+        driver.wait_for_cnc()?;
+        Ok(driver)
+    }
+
+    fn wait_for_cnc(&mut self) -> Result<()> {
+        let deadline = Instant::now() + DRIVER_START_TIMEOUT;
+        let cnc = self.dir.join("cnc.dat");
+        while !cnc.exists() {
+            if let Some(Driver::External(child)) = &mut self.driver {
+                if let Some(status) = child.try_wait()? {
+                    let msg = format!("driver exited with {status}");
+                    return Err(io::Error::other(msg).into());
+                }
+            }
+            if Instant::now() > deadline {
+                let msg = "driver did not create cnc.dat";
+                return Err(io::Error::new(io::ErrorKind::TimedOut, msg).into());
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        Ok(())
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// A context that connects to this driver.
+    pub fn context(&self) -> Result<Context> {
+        Context::builder().dir(self.dir.to_string_lossy()).build()
+    }
+
+    pub fn connect(&self) -> Result<Arc<Aeron>> {
+        Aeron::connect(self.context()?)
+    }
+}
+
+impl Drop for TestDriver {
+    fn drop(&mut self) {
+        match self.driver.take() {
+            #[cfg(feature = "embedded-driver")]
+            Some(Driver::Embedded(driver)) => drop(driver),
+            Some(Driver::External(mut child)) => {
+                child.kill().ok();
+                child.wait().ok();
+            }
+            None => {}
+        }
+        fs::remove_dir_all(&self.dir).ok();
+    }
+}
+
+fn unique_dir() -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    env::temp_dir().join(format!("aeron-test-{}-{n}", std::process::id()))
+}
+
+/// Runs a future to completion on the current thread.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Arc::new(ThreadWaker(thread::current())).into();
+    let mut ctx = task::Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        match future.as_mut().poll(&mut ctx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
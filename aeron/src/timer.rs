@@ -0,0 +1,201 @@
+//! A timer thread shared by the futures of this crate, so they can back off
+//! between retries without depending on an async runtime.
+
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock, PoisonError},
+    task::{self, Poll, Waker},
+    thread,
+    time::Instant,
+};
+
+/// Resolves once its deadline has passed.
+///
+/// The delay is registered with the timer on its first pending poll. Later
+/// polls only update the waker.
+pub(crate) struct Delay {
+    deadline: Instant,
+    registration: Option<Arc<Mutex<Registration>>>,
+}
+
+/// The state of a delay shared with the timer thread.
+struct Registration {
+    waker: Option<Waker>,
+    fired: bool,
+}
+
+impl Delay {
+    pub(crate) fn until(deadline: Instant) -> Self {
+        Delay { deadline, registration: None }
+    }
+}
+
+impl Future for Delay {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if Instant::now() >= this.deadline {
+            return Poll::Ready(());
+        }
+        match &this.registration {
+            Some(registration) => {
+                let mut registration = lock(registration);
+                // The timer may have fired since the deadline was checked.
+                if registration.fired {
+                    return Poll::Ready(());
+                }
+                if !registration.waker.as_ref().is_some_and(|w| w.will_wake(ctx.waker())) {
+                    registration.waker = Some(ctx.waker().clone());
+                }
+            }
+            None => {
+                let registration = Arc::new(Mutex::new(Registration {
+                    waker: Some(ctx.waker().clone()),
+                    fired: false,
+                }));
+                timer().register(this.deadline, registration.clone());
+                this.registration = Some(registration);
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for Delay {
+    fn drop(&mut self) {
+        // Releases the waker, the timer drops its entry once it is due.
+        if let Some(registration) = &self.registration {
+            lock(registration).waker = None;
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+struct Timer {
+    entries: Mutex<BinaryHeap<Entry>>,
+    condvar: Condvar,
+}
+
+struct Entry {
+    deadline: Instant,
+    registration: Arc<Mutex<Registration>>,
+}
+
+// Ordered so that the heap yields the earliest deadline first.
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.deadline.cmp(&self.deadline)
+    }
+}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.deadline == other.deadline
+    }
+}
+
+impl Eq for Entry {}
+
+fn timer() -> &'static Timer {
+    static TIMER: OnceLock<Timer> = OnceLock::new();
+    TIMER.get_or_init(|| {
+        thread::Builder::new()
+            .name("aeron-timer".to_owned())
+            .spawn(|| timer().run())
+            .expect("failed to spawn the timer thread");
+        Timer { entries: Mutex::new(BinaryHeap::new()), condvar: Condvar::new() }
+    })
+}
+
+impl Timer {
+    fn register(&self, deadline: Instant, registration: Arc<Mutex<Registration>>) {
+        let mut entries = lock(&self.entries);
+        let earliest = entries.peek().is_none_or(|entry| deadline < entry.deadline);
+        entries.push(Entry { deadline, registration });
+        if earliest {
+            self.condvar.notify_one();
+        }
+    }
+
+    fn run(&self) {
+        let mut expired = Vec::new();
+        let mut entries = lock(&self.entries);
+        loop {
+            let now = Instant::now();
+            while entries.peek().is_some_and(|entry| entry.deadline <= now) {
+                let Some(entry) = entries.pop() else { break };
+                let mut registration = lock(&entry.registration);
+                registration.fired = true;
+                expired.extend(registration.waker.take());
+            }
+            if !expired.is_empty() {
+                // Wake outside the lock, a waker may register the next delay.
+                drop(entries);
+                expired.drain(..).for_each(Waker::wake);
+                entries = lock(&self.entries);
+                continue;
+            }
+            entries = match entries.peek() {
+                Some(entry) => {
+                    let timeout = entry.deadline - now;
+                    self.condvar
+                        .wait_timeout(entries, timeout)
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0
+                }
+                None => self.condvar.wait(entries).unwrap_or_else(PoisonError::into_inner),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::mpsc,
+        task::Wake,
+        time::{Duration, Instant},
+    };
+
+    struct Notify(Mutex<mpsc::Sender<&'static str>>, &'static str);
+
+    impl Wake for Notify {
+        fn wake(self: Arc<Self>) {
+            lock(&self.0).send(self.1).ok();
+        }
+    }
+
+    #[test]
+    fn wakes_the_latest_waker_once() {
+        let (tx, rx) = mpsc::channel();
+        let first = Waker::from(Arc::new(Notify(Mutex::new(tx.clone()), "first")));
+        let second = Waker::from(Arc::new(Notify(Mutex::new(tx), "second")));
+        let mut delay = Delay::until(Instant::now() + Duration::from_millis(100));
+
+        for waker in [&first, &first, &second] {
+            let mut ctx = task::Context::from_waker(waker);
+            assert!(Pin::new(&mut delay).poll(&mut ctx).is_pending());
+        }
+        let registration = delay.registration.clone().unwrap();
+        assert_eq!(Arc::strong_count(&registration), 3, "registered more than once");
+
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok("second"));
+        assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+        let mut ctx = task::Context::from_waker(&second);
+        assert!(Pin::new(&mut delay).poll(&mut ctx).is_ready());
+    }
+}
@@ -0,0 +1,91 @@
+use aeron::{
+    agent::{Agent, AgentRunner, CompositeAgent},
+    error::Result,
+    idle::Backoff,
+    publication::{OfferResult, Publication},
+    subscription::Subscription,
+    testing::{block_on, TestDriver},
+    StreamId,
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    time::Duration,
+};
+
+const STREAM_ID: StreamId = StreamId(1003);
+const IPC: &str = "aeron:ipc";
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Sender {
+    publication: Publication,
+    remaining: u8,
+}
+
+impl Agent for Sender {
+    fn do_work(&mut self) -> Result<usize> {
+        if self.remaining == 0 {
+            return Ok(0);
+        }
+        match self.publication.offer(&[self.remaining])? {
+            OfferResult::Ok(_) => {
+                self.remaining -= 1;
+                Ok(1)
+            }
+            _ => Ok(0),
+        }
+    }
+
+    fn role_name(&self) -> &str {
+        "sender"
+    }
+}
+
+struct Receiver {
+    subscription: Subscription,
+    received: mpsc::Sender<u8>,
+    closed: Arc<AtomicBool>,
+}
+
+impl Agent for Receiver {
+    fn do_work(&mut self) -> Result<usize> {
+        let received = &self.received;
+        self.subscription.poll(|data, _header| received.send(data[0]).unwrap(), 10)
+    }
+
+    fn on_close(&mut self) -> Result<()> {
+        self.closed.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    fn role_name(&self) -> &str {
+        "receiver"
+    }
+}
+
+#[test]
+fn runner_runs_composite_agent() {
+    let driver = TestDriver::launch().unwrap();
+    let client = driver.connect().unwrap();
+    let subscription = block_on(client.add_subscription(IPC, STREAM_ID).unwrap()).unwrap();
+    let publication = block_on(client.add_publication(IPC, STREAM_ID).unwrap()).unwrap();
+
+    let (tx, rx) = mpsc::channel();
+    let closed = Arc::new(AtomicBool::new(false));
+    let agent = CompositeAgent::new(vec![
+        Box::new(Sender { publication, remaining: 3 }),
+        Box::new(Receiver { subscription, received: tx, closed: closed.clone() }),
+    ]);
+    assert_eq!(agent.role_name(), "[sender,receiver]");
+
+    let runner = AgentRunner::start(agent, Backoff::default(), |e| panic!("{e}")).unwrap();
+    assert_eq!(runner.role_name(), "[sender,receiver]");
+    let received: Vec<u8> = (0..3).map(|_| rx.recv_timeout(TIMEOUT).unwrap()).collect();
+    assert_eq!(received, [3, 2, 1]);
+
+    assert!(runner.is_running());
+    runner.close();
+    assert!(closed.load(Ordering::Acquire));
+}
@@ -0,0 +1,68 @@
+use aeron::{
+    error::Error,
+    publication::{NotConnectedPolicy, SendOptions},
+    sink::PublicationSink,
+    testing::{block_on, TestDriver},
+    StreamId,
+};
+use bytes::Bytes;
+use futures::{stream, SinkExt, StreamExt};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+const STREAM_ID: StreamId = StreamId(1002);
+const IPC: &str = "aeron:ipc";
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[test]
+fn sink_sends_all_items() {
+    let driver = TestDriver::launch().unwrap();
+    let client = driver.connect().unwrap();
+    let subscription = block_on(client.add_subscription(IPC, STREAM_ID).unwrap()).unwrap();
+    let publication = block_on(client.add_publication(IPC, STREAM_ID).unwrap()).unwrap();
+
+    let mut sink = PublicationSink::new(publication);
+    let items = ["a", "b", "c"].map(|item| Ok(Bytes::from_static(item.as_bytes())));
+    block_on(sink.send_all(&mut stream::iter(items))).unwrap();
+
+    let deadline = Instant::now() + TIMEOUT;
+    let mut received = Vec::new();
+    while received.len() < 3 {
+        assert!(Instant::now() < deadline, "timed out");
+        subscription.poll(|data, _header| received.push(data.to_vec()), 10).unwrap();
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(received, [b"a".to_vec(), b"b".to_vec(), b"c".to_vec()]);
+}
+
+#[test]
+fn sink_fails_when_not_connected() {
+    let driver = TestDriver::launch().unwrap();
+    let client = driver.connect().unwrap();
+    let publication = block_on(client.add_publication(IPC, STREAM_ID).unwrap()).unwrap();
+
+    let options = SendOptions::default().not_connected(NotConnectedPolicy::Fail);
+    let mut sink = PublicationSink::with_options(publication, options);
+    let result = block_on(sink.send(Bytes::from_static(b"nobody")));
+    assert!(matches!(result, Err(Error::NotConnected)));
+}
+
+#[test]
+fn stream_yields_messages() {
+    let driver = TestDriver::launch().unwrap();
+    let client = driver.connect().unwrap();
+    let subscription = block_on(client.add_subscription(IPC, STREAM_ID).unwrap()).unwrap();
+    let publication = block_on(client.add_publication(IPC, STREAM_ID).unwrap()).unwrap();
+
+    let mut sink = PublicationSink::new(publication);
+    block_on(sink.send(Bytes::from_static(b"one"))).unwrap();
+    block_on(sink.send(Bytes::from_static(b"two"))).unwrap();
+
+    let mut stream = subscription.into_stream().fragment_limit(1);
+    let (first, header) = block_on(stream.next()).unwrap().unwrap();
+    let (second, _) = block_on(stream.next()).unwrap().unwrap();
+    assert_eq!((first.as_ref(), second.as_ref()), (&b"one"[..], &b"two"[..]));
+    assert_eq!(header.stream_id(), STREAM_ID);
+}
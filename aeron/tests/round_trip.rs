@@ -0,0 +1,497 @@
+use aeron::{
+    client::{Aeron, CloseReason},
+    context::Context,
+    error::Error,
+    fragment_assembler::{ControlledFragmentAssembler, FragmentAssembler},
+    publication::{NotConnectedPolicy, OfferResult, Publication, SendOptions},
+    subscription::{HandlerAction, Subscription},
+    testing::{block_on, TestDriver},
+    CounterId, Position, StreamId,
+};
+use std::{
+    io::IoSlice,
+    sync::{Arc, Mutex, OnceLock, Weak},
+    thread,
+    time::{Duration, Instant},
+};
+
+const STREAM_ID: StreamId = StreamId(1001);
+const IPC: &str = "aeron:ipc";
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// IPC and a UDP channel on `port`, so a test covers both transports. Tests run
+/// in parallel, so each needs a port of its own.
+fn channels(port: u16) -> [String; 2] {
+    [IPC.to_owned(), format!("aeron:udp?endpoint=localhost:{port}")]
+}
+
+fn wait_until(mut condition: impl FnMut() -> bool) {
+    let deadline = Instant::now() + TIMEOUT;
+    while !condition() {
+        assert!(Instant::now() < deadline, "timed out");
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+fn connect(client: &Arc<Aeron>, channel: &str) -> (Publication, Subscription) {
+    let subscription = block_on(client.add_subscription(channel, STREAM_ID).unwrap()).unwrap();
+    let publication = block_on(client.add_publication(channel, STREAM_ID).unwrap()).unwrap();
+    wait_until(|| publication.is_connected() && subscription.is_connected());
+    (publication, subscription)
+}
+
+fn offer(publication: &mut Publication, data: &[u8]) -> Position {
+    let mut position = None;
+    wait_until(|| match publication.offer(data).unwrap() {
+        OfferResult::Ok(p) => {
+            position = Some(p);
+            true
+        }
+        _ => false,
+    });
+    position.unwrap()
+}
+
+fn receive(subscription: &Subscription, count: usize) -> Vec<Vec<u8>> {
+    let mut received = Vec::new();
+    wait_until(|| {
+        let before = received.len();
+        let fragments = subscription.poll(|data, _header| received.push(data.to_vec()), 10);
+        assert_eq!(fragments.unwrap(), received.len() - before);
+        received.len() >= count
+    });
+    received
+}
+
+#[test]
+fn offer_and_poll() {
+    let driver = TestDriver::launch().unwrap();
+    let client = driver.connect().unwrap();
+    for channel in channels(24325) {
+        let (mut publication, subscription) = connect(&client, &channel);
+
+        offer(&mut publication, b"hello");
+        offer(&mut publication, b"world");
+
+        assert_eq!(receive(&subscription, 2), [b"hello".to_vec(), b"world".to_vec()], "{channel}");
+    }
+}
+
+#[test]
+fn offer_reports_oversized_messages() {
+    let driver = TestDriver::launch().unwrap();
+    let client = driver.connect().unwrap();
+    let (mut publication, _subscription) = connect(&client, IPC);
+
+    // The maximum message length is an eighth of the 64 KiB term length.
+    let result = publication.offer(&[0; 16 * 1024]);
+    match result {
+        Err(Error::Aeron { message, .. }) => {
+            assert!(message.contains("max_message_length"), "{message}")
+        }
+        Err(e) => panic!("unexpected error {e}"),
+        Ok(_) => panic!("oversized offer succeeded"),
+    }
+}
+
+#[test]
+fn try_claim_and_commit() {
+    let driver = TestDriver::launch().unwrap();
+    let client = driver.connect().unwrap();
+    for channel in channels(24330) {
+        let (mut publication, subscription) = connect(&client, &channel);
+
+        let mut claim = None;
+        wait_until(|| {
+            claim = publication.try_claim(5).unwrap().into_result().unwrap();
+            claim.is_some()
+        });
+        let (mut buffer, _) = claim.unwrap();
+        buffer.data().copy_from_slice(b"claim");
+        buffer.commit().unwrap();
+
+        assert_eq!(receive(&subscription, 1), [b"claim".to_vec()], "{channel}");
+    }
+}
+
+#[test]
+fn controlled_poll_abort_redelivers() {
+    let driver = TestDriver::launch().unwrap();
+    let client = driver.connect().unwrap();
+    for channel in channels(24331) {
+        let (mut publication, subscription) = connect(&client, &channel);
+
+        let position = offer(&mut publication, b"again");
+
+        let mut attempts = 0;
+        let mut received = Vec::new();
+        wait_until(|| {
+            subscription
+                .controlled_poll(
+                    |data: &[u8], header| {
+                        attempts += 1;
+                        if attempts == 1 {
+                            return HandlerAction::Abort;
+                        }
+                        assert!(header.is_begin() && header.is_end());
+                        assert_eq!(header.position(), position);
+                        received.push(data.to_vec());
+                        HandlerAction::Continue
+                    },
+                    10,
+                )
+                .unwrap();
+            !received.is_empty()
+        });
+
+        assert_eq!(attempts, 2, "{channel}");
+        assert_eq!(received, [b"again".to_vec()], "{channel}");
+    }
+}
+
+#[test]
+fn block_poll_receives_frames() {
+    let driver = TestDriver::launch().unwrap();
+    let client = driver.connect().unwrap();
+    for channel in channels(24332) {
+        let (mut publication, subscription) = connect(&client, &channel);
+
+        offer(&mut publication, b"block");
+
+        let mut block = Vec::new();
+        wait_until(|| {
+            let bytes = subscription
+                .block_poll(|data, _session_id, _term_id| block.extend_from_slice(data), 4096)
+                .unwrap();
+            assert_eq!(bytes, block.len());
+            !block.is_empty()
+        });
+
+        // The block holds complete frames, i.e. the 32 byte data header
+        // followed by the payload.
+        assert_eq!(&block[32..37], b"block", "{channel}");
+    }
+}
+
+#[test]
+fn poll_assembled_reassembles_large_messages() {
+    let driver = TestDriver::launch().unwrap();
+    let client = driver.connect().unwrap();
+    let (mut publication, subscription) = connect(&client, IPC);
+
+    // Larger than the default MTU, so the message is split into fragments.
+    let message: Vec<u8> = (0..4096).map(|i| i as u8).collect();
+    offer(&mut publication, &message);
+    offer(&mut publication, b"small");
+
+    let mut assembler = FragmentAssembler::new();
+    let mut received = Vec::new();
+    wait_until(|| {
+        subscription
+            .poll_assembled(&mut assembler, |data, _header| received.push(data.to_vec()), 10)
+            .unwrap();
+        received.len() >= 2
+    });
+    assert_eq!(received, [message, b"small".to_vec()]);
+}
+
+#[test]
+fn controlled_poll_assembled_redelivers_aborted_messages() {
+    let driver = TestDriver::launch().unwrap();
+    let client = driver.connect().unwrap();
+    let (mut publication, subscription) = connect(&client, IPC);
+
+    let message: Vec<u8> = (0..4096).map(|i| i as u8).collect();
+    offer(&mut publication, &message);
+
+    let mut assembler = ControlledFragmentAssembler::new();
+    let mut attempts = 0;
+    let mut received = Vec::new();
+    wait_until(|| {
+        subscription
+            .controlled_poll_assembled(
+                &mut assembler,
+                |data, _header| {
+                    attempts += 1;
+                    if attempts == 1 {
+                        return HandlerAction::Abort;
+                    }
+                    received.push(data.to_vec());
+                    HandlerAction::Continue
+                },
+                10,
+            )
+            .unwrap();
+        !received.is_empty()
+    });
+    assert_eq!(attempts, 2);
+    assert_eq!(received, [message]);
+}
+
+#[test]
+fn images_poll_per_session() {
+    let driver = TestDriver::launch().unwrap();
+    let client = driver.connect().unwrap();
+    let (mut publication, subscription) = connect(&client, IPC);
+    let position = offer(&mut publication, b"image");
+
+    let images = subscription.images().unwrap();
+    assert_eq!(images.len(), 1);
+    let image = subscription.image_by_session_id(images[0].session_id()).unwrap();
+    assert_eq!(image.source_identity(), "aeron:ipc");
+    assert_eq!(image.join_position(), Position(0));
+
+    let mut received = Vec::new();
+    wait_until(|| {
+        image.poll(|data, _header| received.push(data.to_vec()), 10).unwrap();
+        !received.is_empty()
+    });
+    assert_eq!(received, [b"image".to_vec()]);
+    assert_eq!(image.position(), position);
+    assert!(!image.is_end_of_stream());
+}
+
+#[test]
+fn image_handlers_see_publishers() {
+    let driver = TestDriver::launch().unwrap();
+    let client = driver.connect().unwrap();
+
+    let available = Arc::new(Mutex::new(Vec::new()));
+    let subscription = block_on(
+        client
+            .add_subscription(IPC, STREAM_ID)
+            .unwrap()
+            .on_available_image({
+                let available = available.clone();
+                move |image| {
+                    let source = (image.session_id(), image.source_identity());
+                    available.lock().unwrap().push(source);
+                }
+            })
+            .on_unavailable_image(|_image| {}),
+    )
+    .unwrap();
+    let publication = block_on(client.add_publication(IPC, STREAM_ID).unwrap()).unwrap();
+    wait_until(|| !available.lock().unwrap().is_empty());
+
+    let session_id = subscription.images().unwrap()[0].session_id();
+    assert_eq!(*available.lock().unwrap(), [(session_id, "aeron:ipc".to_owned())]);
+
+    drop(publication);
+    block_on(subscription.close().unwrap()).unwrap();
+}
+
+#[test]
+fn subscription_destination() {
+    let driver = TestDriver::launch().unwrap();
+    let client = driver.connect().unwrap();
+    let destination = "aeron:udp?endpoint=localhost:24326";
+
+    let subscription = Arc::new(
+        block_on(client.add_subscription("aeron:udp?control-mode=manual", STREAM_ID).unwrap())
+            .unwrap(),
+    );
+    let add = subscription.add_destination(destination).unwrap();
+    wait_until(|| add.poll().unwrap());
+
+    let mut publication =
+        block_on(client.add_publication(destination, STREAM_ID).unwrap()).unwrap();
+    wait_until(|| publication.is_connected());
+    offer(&mut publication, b"mds");
+    assert_eq!(receive(&subscription, 1), [b"mds".to_vec()]);
+
+    let remove = subscription.remove_destination(destination).unwrap();
+    wait_until(|| remove.poll().unwrap());
+}
+
+#[test]
+fn publication_destination() {
+    let driver = TestDriver::launch().unwrap();
+    let client = driver.connect().unwrap();
+    let destination = "aeron:udp?endpoint=localhost:24327";
+
+    let subscription = block_on(client.add_subscription(destination, STREAM_ID).unwrap()).unwrap();
+    let mut publication = Arc::new(
+        block_on(client.add_publication("aeron:udp?control-mode=manual", STREAM_ID).unwrap())
+            .unwrap(),
+    );
+    let add = publication.add_destination(destination).unwrap();
+    wait_until(|| add.poll().unwrap());
+    drop(add);
+
+    wait_until(|| publication.is_connected());
+    offer(Arc::get_mut(&mut publication).unwrap(), b"mdc");
+    assert_eq!(receive(&subscription, 1), [b"mdc".to_vec()]);
+
+    let remove = publication.remove_destination(destination).unwrap();
+    wait_until(|| remove.poll().unwrap());
+}
+
+#[test]
+fn offer_vectored_with_reserved_value() {
+    let driver = TestDriver::launch().unwrap();
+    let client = driver.connect().unwrap();
+    let (mut publication, subscription) = connect(&client, IPC);
+
+    let bufs = [IoSlice::new(b"head"), IoSlice::new(b"er"), IoSlice::new(b"")];
+    wait_until(|| {
+        let result = publication
+            .offer_vectored_with_reserved_value_supplier(&bufs, |frame| frame.len() as i64);
+        matches!(result.unwrap(), OfferResult::Ok(_))
+    });
+
+    let mut received = Vec::new();
+    wait_until(|| {
+        subscription
+            .poll(|data, header| received.push((data.to_vec(), header.reserved_value())), 10)
+            .unwrap();
+        !received.is_empty()
+    });
+    // The supplier sees the whole frame, i.e. the data header and the payload.
+    assert_eq!(received, [(b"header".to_vec(), 32 + 6)]);
+}
+
+#[test]
+fn exclusive_publication_offers() {
+    let driver = TestDriver::launch().unwrap();
+    let client = driver.connect().unwrap();
+    let subscription = block_on(client.add_subscription(IPC, STREAM_ID).unwrap()).unwrap();
+    let mut publication =
+        block_on(client.add_exclusive_publication(IPC, STREAM_ID).unwrap()).unwrap();
+    wait_until(|| publication.is_connected());
+
+    let bufs = [IoSlice::new(b"head"), IoSlice::new(b"er")];
+    wait_until(|| matches!(publication.offer_vectored(&bufs).unwrap(), OfferResult::Ok(_)));
+    wait_until(|| matches!(publication.append_padding(64).unwrap(), OfferResult::Ok(_)));
+    wait_until(|| matches!(publication.offer(b"after").unwrap(), OfferResult::Ok(_)));
+
+    assert_eq!(receive(&subscription, 2), [b"header".to_vec(), b"after".to_vec()]);
+}
+
+#[test]
+fn send_retries_until_connected() {
+    let driver = TestDriver::launch().unwrap();
+    let client = driver.connect().unwrap();
+    let mut publication = block_on(client.add_publication(IPC, STREAM_ID).unwrap()).unwrap();
+
+    let options = SendOptions::default().not_connected(NotConnectedPolicy::Fail);
+    let result = block_on(publication.send_with_options(b"early", options));
+    assert!(matches!(result, Err(Error::NotConnected)));
+
+    let options = SendOptions::default().timeout(Duration::from_millis(50));
+    let result = block_on(publication.send_with_options(b"early", options));
+    assert!(matches!(result, Err(Error::TimedOut)));
+
+    let subscription = block_on(client.add_subscription(IPC, STREAM_ID).unwrap()).unwrap();
+    block_on(publication.send(b"sent")).unwrap();
+    assert_eq!(receive(&subscription, 1), [b"sent".to_vec()]);
+}
+
+#[test]
+fn close_releases_resources() {
+    let driver = TestDriver::launch().unwrap();
+    let client = driver.connect().unwrap();
+    let (publication, subscription) = connect(&client, IPC);
+
+    assert!(matches!(client.clone().close(), Err(Error::ClientInUse)));
+
+    block_on(publication.close().unwrap()).unwrap();
+    block_on(subscription.close().unwrap()).unwrap();
+    client.close().unwrap();
+}
+
+#[test]
+fn conductor_agent_invoker() {
+    let driver = TestDriver::launch().unwrap();
+    // Without the invoker the conductor has a thread of its own.
+    assert_eq!(driver.connect().unwrap().do_work().unwrap(), 0);
+
+    let client = Arc::new(OnceLock::<Weak<Aeron>>::new());
+    let nested = Arc::new(Mutex::new(Vec::new()));
+    let mut context = Context::builder()
+        .dir(driver.dir().to_string_lossy())
+        .use_conductor_agent_invoker(true)
+        .build()
+        .unwrap();
+    context.set_on_new_publication({
+        let (client, nested) = (client.clone(), nested.clone());
+        move |_| {
+            // Called during a duty cycle, so the nested duty cycle is skipped.
+            let client = client.get().and_then(Weak::upgrade).unwrap();
+            nested.lock().unwrap().push(client.do_work().unwrap());
+        }
+    });
+    let aeron = Aeron::connect(context).unwrap();
+    client.set(Arc::downgrade(&aeron)).unwrap();
+    assert!(aeron.is_using_conductor_agent_invoker());
+
+    // The futures drive the conductor while they are polled.
+    let subscription = block_on(aeron.add_subscription(IPC, STREAM_ID).unwrap()).unwrap();
+    let mut publication = block_on(aeron.add_publication(IPC, STREAM_ID).unwrap()).unwrap();
+    assert_eq!(*nested.lock().unwrap(), [0]);
+
+    wait_until(|| {
+        aeron.invoke().unwrap();
+        publication.is_connected() && subscription.is_connected()
+    });
+    offer(&mut publication, b"invoked");
+    assert_eq!(receive(&subscription, 1), [b"invoked".to_vec()]);
+}
+
+#[test]
+fn counters_reader_and_callbacks() {
+    let driver = TestDriver::launch().unwrap();
+    let available = Arc::new(Mutex::new(Vec::new()));
+    let mut context = driver.context().unwrap();
+    context.set_on_available_counter({
+        let available = available.clone();
+        move |event| {
+            let reader = event.counters_reader;
+            let found = reader.counter_registration_id(event.counter_id).unwrap();
+            available.lock().unwrap().push((event.registration_id, event.counter_id, found));
+        }
+    });
+    let client = Aeron::connect(context).unwrap();
+
+    // The system counters of the driver come first.
+    let counters = client.counters_reader();
+    assert!(counters.max_counter_id().0 > 0);
+    assert_eq!(counters.counter_label(CounterId(0)).unwrap(), "Bytes sent");
+    assert_eq!(counters.counter_type_id(CounterId(0)).unwrap(), 0);
+    assert!(counters.counter_value(CounterId(0)).is_some());
+    assert_eq!(counters.counter_value(CounterId(-1)), None);
+
+    // The driver announces the heartbeat counter of every client it sees.
+    wait_until(|| !available.lock().unwrap().is_empty());
+    let (registration_id, counter_id, found) = available.lock().unwrap()[0];
+    assert_eq!(found, registration_id);
+    assert_eq!(counters.counter_registration_id(counter_id).unwrap(), registration_id);
+    assert!(counters.counter_value(counter_id).is_some());
+}
+
+#[test]
+fn closed_resolves_on_user_close() {
+    let driver = TestDriver::launch().unwrap();
+    let client = driver.connect().unwrap();
+    let closed = client.closed();
+
+    client.close().unwrap();
+    assert_eq!(block_on(closed), CloseReason::UserClose);
+}
+
+#[test]
+fn closed_reports_driver_timeout() {
+    let driver = TestDriver::launch().unwrap();
+    let mut context = Context::builder()
+        .dir(driver.dir().to_string_lossy())
+        .driver_timeout(Duration::from_millis(500))
+        .build()
+        .unwrap();
+    // The default handler would exit the process.
+    context.set_error_handler(|_| {});
+    let client = Aeron::connect(context).unwrap();
+    let closed = client.closed();
+
+    drop(driver);
+    assert!(matches!(block_on(closed), CloseReason::DriverTimeout(_)));
+}